@@ -1,9 +1,231 @@
-use std::{collections::BTreeMap, process::exit};
+use std::{collections::BTreeMap, path::PathBuf, process::exit};
+
+use crate::export::Format;
+
+pub enum Command {
+    Tui,
+    Run {
+        app: String,
+        tag: Option<String>,
+        junit: Option<String>,
+    },
+    ImportCurl {
+        command: String,
+        app: Option<String>,
+    },
+    Export {
+        app: String,
+        request: String,
+        format: Format,
+        token: bool,
+    },
+    ImportPostman {
+        app: String,
+        file: String,
+    },
+    ExportPostman {
+        app: String,
+        file: Option<String>,
+    },
+    OpenApi {
+        app: String,
+        host: Option<String>,
+        file: Option<String>,
+    },
+    Settings {
+        app: String,
+        settings: Option<String>,
+    },
+}
+
+/// The arguments, without the global `--state <path>` option.
+fn args() -> Vec<String> {
+    let mut args = std::env::args().skip(1);
+    let mut kept = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--state" => {
+                args.next();
+            }
+            _ => kept.push(arg),
+        }
+    }
+    kept
+}
+
+/// The state file given with `--state <path>`, anywhere among the arguments.
+pub fn state_path() -> Option<PathBuf> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--state");
+    args.next()?;
+    let path = args.next().expect("--state needs a path");
+    Some(PathBuf::from(path))
+}
+
+/// Subcommands run without the TUI. Without a subcommand the TUI is started.
+pub fn command() -> Command {
+    let args = args();
+
+    match args.first().map(String::as_str) {
+        Some("run") => {
+            let Some(app) = args.get(1) else {
+                println!("Usage: helved run <app> [Options]\n");
+                println!("Options:");
+                println!("-tag        -t    only run requests with this tag");
+                println!("-junit      -j    write a JUnit XML report to this file");
+                exit(0);
+            };
+
+            let mut tag = None;
+            let mut junit = None;
+
+            if !args.len().is_multiple_of(2) {
+                panic!("Invalid number of arguments");
+            }
+
+            args[2..]
+                .chunks(2)
+                .for_each(|chunk| match (chunk[0].as_str(), chunk[1].as_str()) {
+                    ("-tag" | "-t", value) => tag = Some(value.into()),
+                    ("-junit" | "-j", value) => junit = Some(value.into()),
+                    (key, value) => panic!("Invalid argument {key} {value}"),
+                });
+
+            Command::Run {
+                app: app.into(),
+                tag,
+                junit,
+            }
+        }
+        Some("import-curl") => {
+            let Some(command) = args.get(1) else {
+                println!("Usage: helved import-curl '<curl command>' [Options]\n");
+                println!("Options:");
+                println!("-app        -a    save to this app instead of matching the host");
+                exit(0);
+            };
+
+            let app = match args.get(2..).unwrap_or_default() {
+                [] => None,
+                [key, value] if key == "-app" || key == "-a" => Some(value.into()),
+                rest => panic!("Invalid arguments {}", rest.join(" ")),
+            };
+
+            Command::ImportCurl {
+                command: command.into(),
+                app,
+            }
+        }
+        Some("export") => {
+            let (Some(app), Some(request)) = (args.get(1), args.get(2)) else {
+                println!("Usage: helved export <app> <request id|path> [Options]\n");
+                println!("Options:");
+                println!("-format     -f    curl (default), httpie or http");
+                println!("-token      -t    inline a fresh bearer token instead of $TOKEN");
+                exit(0);
+            };
+
+            let mut format = Format::default();
+            let mut token = false;
+            let mut options = args[3..].iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "-format" | "-f" => {
+                        let value = options.next().expect("-format needs a value");
+                        format =
+                            Format::try_from(value.as_str()).unwrap_or_else(|err| panic!("{err}"));
+                    }
+                    "-token" | "-t" => token = true,
+                    option => panic!("Invalid argument {option}"),
+                }
+            }
+
+            Command::Export {
+                app: app.into(),
+                request: request.into(),
+                format,
+                token,
+            }
+        }
+        Some("import-postman") => {
+            let (Some(app), Some(file)) = (args.get(1), args.get(2)) else {
+                println!("Usage: helved import-postman <app> <collection.json>");
+                exit(0);
+            };
+
+            Command::ImportPostman {
+                app: app.into(),
+                file: file.into(),
+            }
+        }
+        Some("export-postman") => {
+            let Some(app) = args.get(1) else {
+                println!("Usage: helved export-postman <app> [collection.json]\n");
+                println!("Writes to stdout when no file is given");
+                exit(0);
+            };
+
+            Command::ExportPostman {
+                app: app.into(),
+                file: args.get(2).cloned(),
+            }
+        }
+        Some("openapi") => {
+            let Some(app) = args.get(1) else {
+                println!("Usage: helved openapi <app> [Options]\n");
+                println!("Generates requests from /v3/api-docs and marks changed or removed operations\n");
+                println!("Options:");
+                println!("-host       -h    fetch from this host, e.g. http://localhost:8080");
+                println!("-file       -f    read the document from this file instead");
+                exit(0);
+            };
+
+            let mut host = None;
+            let mut file = None;
+
+            if !args.len().is_multiple_of(2) {
+                panic!("Invalid number of arguments");
+            }
+
+            args[2..]
+                .chunks(2)
+                .for_each(|chunk| match (chunk[0].as_str(), chunk[1].as_str()) {
+                    ("-host" | "-h", value) => host = Some(value.into()),
+                    ("-file" | "-f", value) => file = Some(value.into()),
+                    (key, value) => panic!("Invalid argument {key} {value}"),
+                });
+
+            Command::OpenApi {
+                app: app.into(),
+                host,
+                file,
+            }
+        }
+        Some("settings") => {
+            let Some(app) = args.get(1) else {
+                println!("Usage: helved settings <app> [key=value ...]\n");
+                println!("Sets the defaults for the app's requests, prints them when none are given\n");
+                println!("Settings:");
+                println!("timeout=30s       total time for a request");
+                println!("connect=10s       time to connect");
+                println!("retries=0         retries of GET, PUT and DELETE on 5xx, timeouts and connect errors");
+                println!("redirects=10      redirects to follow, or on/off");
+                exit(0);
+            };
+
+            Command::Settings {
+                app: app.into(),
+                settings: args.get(2..).filter(|s| !s.is_empty()).map(|s| s.join(" ")),
+            }
+        }
+        _ => Command::Tui,
+    }
+}
 
 pub fn parse() -> Cli {
     let mut cli = Cli::default();
 
-    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let args = args();
     let args_len = args.len();
 
     match args_len {
@@ -18,9 +240,10 @@ pub fn parse() -> Cli {
             println!("-header     -h    add a header to header-list: <key>=<value>");
             println!("-body       -b    request body");
             println!("-filter     -f    filter on k8s secrets");
+            println!("--state           state file, instead of $XDG_DATA_HOME/helved/state.json");
             exit(0);
         }
-        _ if args_len % 2 != 0 => panic!("Invalid number of arguments"),
+        _ if !args_len.is_multiple_of(2) => panic!("Invalid number of arguments"),
         _ => {
             args.chunks(2)
                 .map(|chunk| (chunk[0].to_string(), chunk[1].to_string()))
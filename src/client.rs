@@ -1,17 +1,52 @@
-use crate::{oauth2::{self, OAuth2Client}, state::NaisOAuth2};
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::Context;
+use reqwest::{
+    multipart::{Form, Part},
+    redirect::Policy,
+    RequestBuilder,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{lookup_host, TcpStream},
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+use crate::{
+    oauth2::{self, OAuth2Client},
+    state::{BodyMode, Method, NaisOAuth2, Param, Request, Settings},
+};
 
 pub struct Client {
     client: reqwest::Client,
     host: String,
     token: String,
+    settings: Settings,
 }
 
 impl Client {
-    pub fn new(host: &String, token: String) -> Self {
+    pub fn new(host: &str, token: String, settings: Settings) -> Self {
+        let redirect = match settings.redirects() {
+            0 => Policy::none(),
+            max => Policy::limited(max),
+        };
+        let client = reqwest::Client::builder()
+            .connect_timeout(settings.connect_timeout())
+            .redirect(redirect)
+            .build()
+            .expect("Failed to build http client");
+
         Self {
-            client: reqwest::Client::new(),
+            client,
             host: host.to_string(),
             token,
+            settings,
         }
     }
 
@@ -43,6 +78,379 @@ impl Client {
         println!("{:?}", res.text().await?);
         Ok(())
     }
+
+    /// Sends the request within the timeout. Idempotent requests are retried with a backoff
+    /// when they fail to connect, time out or get a 5xx, as many times as the settings allow.
+    /// Compressed responses are asked for unless the request says otherwise, and decompressed.
+    pub async fn send(&self, req: &Request) -> color_eyre::Result<Response> {
+        self.execute(req, false).await
+    }
+
+    /// Sends the request like `send`, and times the DNS lookup, connect and TLS handshake with a
+    /// probe on a connection of its own first, see `probe`. For sends the user looks at, not for
+    /// runs or load.
+    pub async fn send_timed(&self, req: &Request) -> color_eyre::Result<Response> {
+        self.execute(req, true).await
+    }
+
+    async fn execute(&self, req: &Request, timed: bool) -> color_eyre::Result<Response> {
+        let encoded = req.has_header("accept-encoding");
+        let mut attempt = 0;
+        let mut timing = None;
+        let (mut timing, mut sizes, start, res) = loop {
+            let mut builder = self.builder(req)?.timeout(self.settings.timeout());
+            if !encoded {
+                builder = builder.header("Accept-Encoding", "gzip, deflate, br");
+            }
+            let request = builder.build()?;
+            let sizes = Sizes::sent(&request);
+            if timed && timing.is_none() {
+                timing = Some(probe(request.url(), self.settings.connect_timeout()).await);
+            }
+
+            let start = Instant::now();
+            let result = self.client.execute(request).await;
+            let retry = attempt < self.settings.retries()
+                && req.method.is_idempotent()
+                && match &result {
+                    Ok(res) => res.status().is_server_error(),
+                    Err(err) => err.is_connect() || err.is_timeout(),
+                };
+
+            if !retry {
+                break (timing.unwrap_or_default(), sizes, start, result?);
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        };
+        timing.ttfb = start.elapsed();
+
+        let status = res.status().as_u16();
+        let headers: Vec<(String, String)> = res
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect();
+        let received = res.bytes().await?.to_vec();
+        let elapsed = start.elapsed();
+
+        sizes.response_headers = header_size(headers.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        sizes.response_body = received.len() as u64;
+        sizes.encoding = headers
+            .iter()
+            .find(|(k, v)| k.eq_ignore_ascii_case("content-encoding") && v != "identity")
+            .map(|(_, v)| v.clone());
+        let decoded = sizes
+            .encoding
+            .as_deref()
+            .and_then(|encoding| decode(encoding, &received));
+        sizes.decoded_body = decoded.as_ref().map(|bytes| bytes.len() as u64);
+        let bytes = decoded.unwrap_or(received);
+
+        Ok(Response {
+            status,
+            headers,
+            body: String::from_utf8_lossy(&bytes).to_string(),
+            bytes,
+            elapsed,
+            timing,
+            sizes,
+        })
+    }
+
+    /// Sends the request once, without retries or timing probes, to generate load.
+    /// Returns the status once the body has been read.
+    pub async fn hit(&self, req: &Request) -> color_eyre::Result<u16> {
+        let res = self
+            .builder(req)?
+            .timeout(self.settings.timeout())
+            .send()
+            .await?;
+        let status = res.status().as_u16();
+        res.bytes().await?;
+        Ok(status)
+    }
+
+    /// Streams the response body into the file instead of buffering it, reporting progress as chunks arrive.
+    /// Only the connect timeout applies, as large files may take longer than any request timeout.
+    pub async fn download(
+        &self,
+        req: &Request,
+        path: &Path,
+        progress: Arc<Mutex<Progress>>,
+    ) -> color_eyre::Result<u64> {
+        let mut res = self.builder(req)?.send().await?;
+        {
+            let mut progress = progress.lock().expect("download progress");
+            progress.status = Some(res.status().as_u16());
+            progress.total = res.content_length();
+        }
+
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .wrap_err_with(|| format!("create {}", path.display()))?;
+        let mut received = 0;
+        while let Some(chunk) = res.chunk().await? {
+            file.write_all(&chunk).await?;
+            received += chunk.len() as u64;
+            progress.lock().expect("download progress").received = received;
+        }
+        file.flush().await?;
+        Ok(received)
+    }
+
+    fn builder(&self, req: &Request) -> color_eyre::Result<RequestBuilder> {
+        let host = match self.host.starts_with("http") {
+            true => self.host.clone(),
+            false => format!("https://{}", self.host),
+        };
+        let path = match req.path.starts_with('/') {
+            true => req.path_with_query(),
+            false => format!("/{}", req.path_with_query()),
+        };
+
+        let mut builder = self
+            .client
+            .request(reqwest::Method::from(&req.method), format!("{host}{path}"));
+        if !req.has_header("authorization") {
+            builder = builder.header("Authorization", format!("Bearer {}", self.token));
+        }
+
+        for header in req.headers.iter().filter(|h| h.enabled) {
+            builder = builder.header(&header.key, &header.value);
+        }
+
+        let overridden = req.has_header("content-type");
+
+        if let Some(content_type) = req.body_mode.content_type().filter(|_| !overridden) {
+            builder = builder.header("Content-Type", content_type);
+        }
+
+        if req.has_body() {
+            builder = match req.body_mode {
+                BodyMode::Json | BodyMode::Raw => builder.body(req.body.clone()),
+                BodyMode::Form => builder.body(Param::encode(&req.form)),
+                BodyMode::Multipart => builder.multipart(multipart(&req.form)?),
+                BodyMode::File => builder
+                    .body(fs::read(&req.body).wrap_err_with(|| format!("read {}", req.body))?),
+            };
+        }
+
+        Ok(builder)
+    }
+}
+
+/// The wait before a retry, doubling from 200 ms up to 12.8 s.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(200u64.saturating_mul(1 << attempt.min(6)))
+}
+
+/// Times the DNS lookup, TCP connect and TLS handshake on a connection of its own, as reqwest
+/// doesn't expose these phases of the request. It runs once per timed send, not for each retry,
+/// and its numbers are for the probe rather than the connection the request used. Phases that
+/// fail or don't apply are left out.
+async fn probe(url: &reqwest::Url, limit: Duration) -> Timing {
+    let mut timing = Timing::default();
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return timing;
+    };
+
+    let start = Instant::now();
+    let Ok(Ok(mut addrs)) = tokio::time::timeout(limit, lookup_host((host, port))).await else {
+        return timing;
+    };
+    timing.dns = Some(start.elapsed());
+
+    let Some(addr) = addrs.next() else {
+        return timing;
+    };
+    let start = Instant::now();
+    let Ok(Ok(stream)) = tokio::time::timeout(limit, TcpStream::connect(addr)).await else {
+        return timing;
+    };
+    timing.connect = Some(start.elapsed());
+
+    if url.scheme() == "https" {
+        let Ok(connector) = native_tls::TlsConnector::new().map(TlsConnector::from) else {
+            return timing;
+        };
+        let start = Instant::now();
+        let handshake = connector.connect(host, stream);
+        if let Ok(Ok(_)) = tokio::time::timeout(limit, handshake).await {
+            timing.tls = Some(start.elapsed());
+        }
+    }
+    timing
+}
+
+/// Decompresses a body sent with a `Content-Encoding`, where stacked encodings like `gzip, br`
+/// were applied in order and are undone in reverse. None if an encoding isn't supported or the
+/// body isn't valid for it.
+fn decode(encoding: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut body = bytes.to_vec();
+    for encoding in encoding.split(',').map(str::trim).rev() {
+        let mut decoded = vec![];
+        match encoding.to_ascii_lowercase().as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => flate2::read::GzDecoder::new(&body[..]).read_to_end(&mut decoded),
+            "deflate" => flate2::read::ZlibDecoder::new(&body[..]).read_to_end(&mut decoded),
+            "br" => brotli::Decompressor::new(&body[..], 4096).read_to_end(&mut decoded),
+            _ => return None,
+        }
+        .ok()?;
+        body = decoded;
+    }
+    Some(body)
+}
+
+/// The size of the headers as they are written on the wire, `key: value\r\n` each.
+fn header_size<'a>(headers: impl Iterator<Item = (&'a str, &'a str)>) -> u64 {
+    headers.map(|(k, v)| (k.len() + v.len() + 4) as u64).sum()
+}
+
+/// A multipart form where `@path` values are read from disk as file parts.
+fn multipart(params: &[Param]) -> color_eyre::Result<Form> {
+    let mut form = Form::new();
+    for param in params.iter().filter(|p| p.enabled) {
+        form = match param.file() {
+            Some(path) => {
+                let bytes = fs::read(path).wrap_err_with(|| format!("read {path}"))?;
+                let name = Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mime = mime_guess::from_path(path).first_or_octet_stream();
+                let part = Part::bytes(bytes).file_name(name).mime_str(mime.as_ref())?;
+                form.part(param.key.clone(), part)
+            }
+            None => form.text(param.key.clone(), param.value.clone()),
+        };
+    }
+    Ok(form)
+}
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body as text, with invalid UTF-8 replaced.
+    pub body: String,
+    pub bytes: Vec<u8>,
+    pub elapsed: Duration,
+    pub timing: Timing,
+    pub sizes: Sizes,
+}
+
+/// How long each phase of the request took. DNS, connect and TLS are measured by a separate
+/// probe before a timed send, see `probe`, and left out otherwise. Time to first byte is from
+/// sending until the response headers arrived.
+#[derive(Clone, Debug, Default)]
+pub struct Timing {
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub tls: Option<Duration>,
+    pub ttfb: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Sizes {
+    pub request_headers: u64,
+    pub request_body: u64,
+    pub response_headers: u64,
+    /// The body as received, which is still compressed when the server used a content encoding.
+    pub response_body: u64,
+    /// The body once decompressed, when it had a content encoding.
+    pub decoded_body: Option<u64>,
+    /// The `Content-Encoding` of the response, e.g. `gzip`.
+    pub encoding: Option<String>,
+}
+
+impl Sizes {
+    /// The sizes of the request about to be sent, streamed bodies count as their content length.
+    fn sent(request: &reqwest::Request) -> Self {
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.to_str().unwrap_or_default()));
+        let body = request.body().map(|body| match body.as_bytes() {
+            Some(bytes) => bytes.len() as u64,
+            None => request
+                .headers()
+                .get("content-length")
+                .and_then(|len| len.to_str().ok()?.parse().ok())
+                .unwrap_or_default(),
+        });
+
+        Self {
+            request_headers: header_size(headers),
+            request_body: body.unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Response {
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the body should be previewed as bytes rather than text.
+    pub fn is_binary(&self) -> bool {
+        let textual = ["text/", "json", "xml", "javascript", "urlencoded"];
+        match self.header("content-type") {
+            Some(ct) => !textual.iter().any(|t| ct.contains(t)),
+            None => std::str::from_utf8(&self.bytes).is_err(),
+        }
+    }
+
+    /// The file name suggested by a `Content-Disposition: attachment; filename="..."` header.
+    pub fn filename(&self) -> Option<String> {
+        let disposition = self.header("content-disposition")?;
+        let (_, name) = disposition.split_once("filename=")?;
+        let name = name.split(';').next()?.trim().trim_matches('"');
+        Path::new(name)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+    }
+
+    /// The file extension for the content type, e.g. `csv` for `text/csv`.
+    pub fn extension(&self) -> &str {
+        let content_type = self.header("content-type").unwrap_or_default();
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        match essence {
+            "application/json" => "json",
+            "text/plain" => "txt",
+            _ => mime_guess::get_mime_extensions_str(essence)
+                .and_then(|exts| exts.first().copied())
+                .unwrap_or("bin"),
+        }
+    }
+}
+
+/// Shared with the UI while a download runs in the background.
+#[derive(Default, Debug)]
+pub struct Progress {
+    pub path: PathBuf,
+    pub status: Option<u16>,
+    pub received: u64,
+    pub total: Option<u64>,
+    /// The bytes written, or the error, once the download has finished.
+    pub done: Option<Result<u64, String>>,
+}
+
+impl From<&Method> for reqwest::Method {
+    fn from(value: &Method) -> Self {
+        match value {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Put => reqwest::Method::PUT,
+            Method::Patch => reqwest::Method::PATCH,
+            Method::Delete => reqwest::Method::DELETE,
+        }
+    }
 }
 
 pub async fn token(app: &str, ns: &str, azure: NaisOAuth2) -> color_eyre::Result<String> {
@@ -57,3 +465,67 @@ pub async fn token(app: &str, ns: &str, azure: NaisOAuth2) -> color_eyre::Result
     let token = client.get_token(client_credential).await?;
     Ok(token.access_token)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::state::Header;
+
+    use super::*;
+
+    #[test]
+    fn own_authorization_replaces_the_bearer() {
+        let client = Client::new("localhost", "abc".into(), Settings::default());
+        let mut req = Request::new(Method::Get, "/", vec![], "");
+        let bearer = client.builder(&req).unwrap().build().unwrap();
+        req.headers.push(Header::new(
+            "Authorization".into(),
+            "Basic dXNlcjpwYXNz".into(),
+        ));
+        let basic = client.builder(&req).unwrap().build().unwrap();
+
+        let authorization = |request: &reqwest::Request| {
+            let values = request.headers().get_all("authorization").iter();
+            values
+                .map(|v| v.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(authorization(&bearer), vec!["Bearer abc"]);
+        assert_eq!(authorization(&basic), vec!["Basic dXNlcjpwYXNz"]);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff(0), Duration::from_millis(200));
+        assert_eq!(backoff(2), Duration::from_millis(800));
+        assert_eq!(backoff(100), Duration::from_millis(12_800));
+    }
+
+    #[test]
+    fn decode_gzip() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"{\"status\":\"OK\"}").unwrap();
+        let gzip = encoder.finish().unwrap();
+
+        assert_eq!(decode("gzip", &gzip).unwrap(), b"{\"status\":\"OK\"}");
+        assert_eq!(decode("gzip", b"not gzip"), None);
+        assert_eq!(decode("zstd", &gzip), None);
+    }
+
+    #[test]
+    fn decode_stacked_encodings() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"{\"status\":\"OK\"}").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let mut both = vec![];
+        let mut encoder = brotli::CompressorWriter::new(&mut both, 4096, 5, 22);
+        encoder.write_all(&gzip).unwrap();
+        drop(encoder);
+
+        assert_eq!(decode("gzip, br", &both).unwrap(), b"{\"status\":\"OK\"}");
+        assert_eq!(decode("br, gzip", &both), None);
+    }
+}
@@ -0,0 +1,153 @@
+use color_eyre::eyre::{bail, Result};
+
+use crate::keymap::{self, Action, Context};
+
+/// A line typed after `:`, either an action of the view by name, like `send` or `move-down`,
+/// or one of the commands without a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Action(Action),
+    /// Switches the kube context, like `env prod`.
+    Env(String),
+    /// Switches the namespace the apps are listed from.
+    Ns(String),
+    ImportCurl(String),
+    ExportPostman(Option<String>),
+    Logs,
+    Restart,
+    /// Switches to the theme, or the next one.
+    Theme(Option<String>),
+    /// Filters the view like `/`, an empty query clears the filter.
+    Filter(String),
+}
+
+/// The commands without a key and what they take.
+pub const COMMANDS: [(&str, &str); 8] = [
+    ("env", "<context>"),
+    ("ns", "<namespace>"),
+    ("import", "curl <command>"),
+    ("export", "postman [file]"),
+    ("logs", ""),
+    ("restart", ""),
+    ("theme", "[name]"),
+    ("filter", "<query>"),
+];
+
+pub fn parse(line: &str, context: Context) -> Result<Command> {
+    let line = line.trim();
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+
+    let command = match (name, arg) {
+        ("env", "") | ("ns", "") => bail!("{name} needs a name"),
+        ("env", context) => Command::Env(context.into()),
+        ("ns", ns) => Command::Ns(ns.into()),
+        ("import", arg) if arg.starts_with("curl ") => Command::ImportCurl(arg.into()),
+        ("export", arg) if arg.split_whitespace().next() == Some("postman") => {
+            let file = arg.split_whitespace().nth(1).map(String::from);
+            Command::ExportPostman(file)
+        }
+        ("logs", "") => Command::Logs,
+        ("restart", "") => Command::Restart,
+        ("theme", "") => Command::Theme(None),
+        ("theme", name) => Command::Theme(Some(name.into())),
+        ("filter", query) => Command::Filter(query.into()),
+        (name, "") => match Action::named(name).filter(|a| actions(context).contains(a)) {
+            Some(action) => Command::Action(action),
+            None => bail!("unknown command {name}"),
+        },
+        (name, _) => bail!("{name} takes no arguments"),
+    };
+    Ok(command)
+}
+
+/// The actions that can be run by name in the context.
+fn actions(context: Context) -> Vec<Action> {
+    let keymap = keymap::current();
+    [context, Context::Global]
+        .into_iter()
+        .flat_map(|context| keymap.actions(context))
+        .map(|(action, _)| action)
+        .filter(|action| *action != Action::Command)
+        .collect()
+}
+
+/// The lines the typed one can be completed to, `names` gives the choices for the argument of a
+/// command, like the themes for `theme`.
+pub fn complete(line: &str, context: Context, names: impl Fn(&str) -> Vec<String>) -> Vec<String> {
+    match line.split_once(' ') {
+        None => {
+            let names = COMMANDS
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .chain(actions(context).into_iter().map(|a| a.name()));
+            let mut lines: Vec<String> = vec![];
+            for name in names.filter(|name| name.starts_with(line)) {
+                if !lines.contains(&name) {
+                    lines.push(name);
+                }
+            }
+            lines
+        }
+        Some((name, arg)) => names(name)
+            .into_iter()
+            .filter(|choice| choice.starts_with(arg.trim_start()))
+            .map(|choice| format!("{name} {choice}"))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands_and_actions() {
+        let parse = |line| parse(line, Context::Requests).unwrap();
+
+        assert_eq!(parse("send"), Command::Action(Action::Send));
+        assert_eq!(parse(" move-down "), Command::Action(Action::MoveDown));
+        assert_eq!(parse("env prod"), Command::Env("prod".into()));
+        assert_eq!(
+            parse("import curl -X POST https://a/b"),
+            Command::ImportCurl("curl -X POST https://a/b".into())
+        );
+        assert_eq!(parse("export"), Command::Action(Action::Export));
+        assert_eq!(
+            parse("export postman out.json"),
+            Command::ExportPostman(Some("out.json".into()))
+        );
+        assert_eq!(parse("filter"), Command::Filter("".into()));
+        assert_eq!(
+            parse("theme red-light"),
+            Command::Theme(Some("red-light".into()))
+        );
+    }
+
+    #[test]
+    fn only_actions_of_the_view_parse() {
+        assert!(parse("send", Context::Apps).is_err());
+        assert!(parse("ns", Context::Apps).is_err());
+        assert!(parse("logs now", Context::Apps).is_err());
+        assert!(parse("refresh", Context::Apps).is_ok());
+    }
+
+    #[test]
+    fn complete_names_and_arguments() {
+        let names = |name: &str| match name {
+            "theme" => vec!["blue".into(), "blue-light".into(), "red".into()],
+            _ => vec![],
+        };
+
+        assert_eq!(complete("th", Context::Apps, names), vec!["theme"]);
+        assert_eq!(
+            complete("theme bl", Context::Apps, names),
+            vec!["theme blue", "theme blue-light"]
+        );
+        assert_eq!(complete("sen", Context::Requests, names), vec!["send"]);
+        assert_eq!(complete("ex", Context::Requests, names), vec!["export", "expand"]);
+        assert!(complete("sen", Context::Apps, names).is_empty());
+    }
+}
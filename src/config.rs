@@ -0,0 +1,107 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{arg, theme::ThemeConfig};
+
+const APP: &str = "helved";
+
+/// User preferences, read from `$XDG_CONFIG_HOME/helved/config.json`.
+/// The saved apps and requests are data, kept in [`data_dir`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Where to keep the state instead of the data directory.
+    pub state: Option<PathBuf>,
+    /// The number of previous versions of the state kept next to it, as `state.json.1` and up.
+    pub backups: usize,
+    /// The theme to start with, one of the built in ones or of `themes`.
+    pub theme: Option<String>,
+    /// Themes of the user's own, by name.
+    pub themes: BTreeMap<String, ThemeConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            state: None,
+            backups: 5,
+            theme: None,
+            themes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let path = config_dir().join("config.json");
+        match std::fs::read_to_string(&path) {
+            Ok(json) => {
+                serde_json::from_str(&json).wrap_err_with(|| format!("invalid {}", path.display()))
+            }
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    /// The state file given with `--state` or in the config, if any.
+    pub fn state_override(&self) -> Option<PathBuf> {
+        arg::state_path().or_else(|| self.state.clone())
+    }
+
+    /// The state file, `$XDG_DATA_HOME/helved/state.json` unless overridden.
+    pub fn state_path(&self) -> PathBuf {
+        self.state_override()
+            .unwrap_or_else(|| data_dir().join("state.json"))
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    xdg_dir(std::env::var_os("XDG_CONFIG_HOME"), home(), ".config")
+}
+
+pub fn data_dir() -> PathBuf {
+    xdg_dir(std::env::var_os("XDG_DATA_HOME"), home(), ".local/share")
+}
+
+fn home() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+/// The app's directory under an XDG base directory. The spec says to ignore relative paths,
+/// so those fall back to the default under the home directory, like an unset variable.
+fn xdg_dir(var: Option<OsString>, home: Option<PathBuf>, fallback: &str) -> PathBuf {
+    let base = var
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| home.map(|home| home.join(fallback)))
+        .unwrap_or_else(|| Path::new(".").join(fallback));
+    base.join(APP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_dir_falls_back_to_home() {
+        let home = Some(PathBuf::from("/home/me"));
+
+        assert_eq!(
+            xdg_dir(Some("/data".into()), home.clone(), ".local/share"),
+            PathBuf::from("/data/helved")
+        );
+        assert_eq!(
+            xdg_dir(Some("relative".into()), home.clone(), ".local/share"),
+            PathBuf::from("/home/me/.local/share/helved")
+        );
+        assert_eq!(
+            xdg_dir(None, home, ".config"),
+            PathBuf::from("/home/me/.config/helved")
+        );
+    }
+}
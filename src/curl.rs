@@ -0,0 +1,339 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+use color_eyre::eyre::{bail, eyre, Result};
+use reqwest::Url;
+
+use crate::state::{App, BodyMode, Header, Method, Param, Request};
+
+/// A request parsed from a curl command, together with the host it was sent to.
+pub struct Import {
+    pub host: String,
+    pub request: Request,
+}
+
+/// Flags that aren't imported but take a value, which is skipped with them.
+const VALUE_FLAGS: [&str; 30] = [
+    "-o",
+    "--output",
+    "-A",
+    "--user-agent",
+    "-b",
+    "--cookie",
+    "-c",
+    "--cookie-jar",
+    "-e",
+    "--referer",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--cacert",
+    "--capath",
+    "-E",
+    "--cert",
+    "--key",
+    "-w",
+    "--write-out",
+    "-x",
+    "--proxy",
+    "-r",
+    "--range",
+    "--retry",
+    "--resolve",
+    "-K",
+    "--config",
+    "--limit-rate",
+    "--max-redirs",
+];
+
+/// Parses a curl command line as copied from a terminal or a chat.
+/// Supports `-X`, `-H`, `-d`/`--data-raw`, `--data-urlencode`, `-F`, `--json`, `-u` and the URL with query params.
+pub fn parse(command: &str) -> Result<Import> {
+    let mut args = split(command)?.into_iter();
+
+    match args.next().as_deref() {
+        Some("curl") => {}
+        _ => bail!("not a curl command"),
+    }
+
+    let mut method = None;
+    let mut url = None;
+    let mut headers = vec![];
+    let mut body: Option<String> = None;
+    let mut form = vec![];
+    let mut mode = None;
+    let mut user = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| eyre!("{name} needs a value"));
+
+        match arg.as_str() {
+            "-X" | "--request" => method = Some(value(&arg)?),
+            _ if arg.starts_with("-X") => method = Some(arg[2..].to_string()),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (key, val) = header
+                    .split_once(':')
+                    .ok_or_else(|| eyre!("invalid header {header}"))?;
+                headers.push(Header::new(key.trim().into(), val.trim().into()));
+            }
+            "--data-urlencode" => {
+                let data = value(&arg)?;
+                let (key, val) = data.split_once('=').unwrap_or((&data, ""));
+                form.push(Param::new(key, val));
+                mode = Some(BodyMode::Form);
+            }
+            "-F" | "--form" | "--form-string" => {
+                let data = value(&arg)?;
+                let (key, val) = data
+                    .split_once('=')
+                    .ok_or_else(|| eyre!("invalid form field {data}"))?;
+                form.push(Param::new(key, val));
+                mode = Some(BodyMode::Multipart);
+            }
+            "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                let data = value(&arg)?;
+                // only --data-raw sends a leading @ as is, the others read the file
+                if let Some(path) = data.strip_prefix('@').filter(|_| arg != "--data-raw") {
+                    body = Some(path.into());
+                    mode = Some(BodyMode::File);
+                    continue;
+                }
+                body = Some(match body {
+                    Some(prev) => format!("{prev}&{data}"),
+                    None => data,
+                });
+            }
+            "--json" => {
+                body = Some(value(&arg)?);
+                headers.push(Header::new(
+                    "Content-Type".into(),
+                    "application/json".into(),
+                ));
+                headers.push(Header::new("Accept".into(), "application/json".into()));
+            }
+            "-u" | "--user" => user = Some(value(&arg)?),
+            "--url" => url = Some(value(&arg)?),
+            _ if VALUE_FLAGS.contains(&arg.as_str()) => {
+                value(&arg)?;
+            }
+            _ if arg.starts_with('-') => {} // flags like -s, -k, -L and --compressed
+            _ => url = Some(arg),
+        }
+    }
+
+    // curl sends an explicit Authorization header instead of the one for -u
+    let authorization = headers
+        .iter()
+        .any(|h| h.key.eq_ignore_ascii_case("authorization"));
+    if let Some(user) = user.filter(|_| !authorization) {
+        let credentials = BASE64_STANDARD.encode(user);
+        headers.push(Header::new(
+            "Authorization".into(),
+            format!("Basic {credentials}"),
+        ));
+    }
+
+    let url = url.ok_or_else(|| eyre!("missing url"))?;
+    let url = match url.contains("://") {
+        true => Url::parse(&url)?,
+        false => Url::parse(&format!("https://{url}"))?,
+    };
+
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => bail!("missing host in {url}"),
+    };
+
+    let query = url.query().map(Param::parse).unwrap_or_default();
+
+    let method = match method.map(|m| m.to_uppercase()).as_deref() {
+        Some("GET") => Method::Get,
+        Some("POST") => Method::Post,
+        Some("PUT") => Method::Put,
+        Some("PATCH") => Method::Patch,
+        Some("DELETE") => Method::Delete,
+        Some(other) => bail!("unsupported method {other}"),
+        None if body.is_some() || mode.is_some() => Method::Post,
+        None => Method::Get,
+    };
+
+    let content_type = headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.to_lowercase());
+    let mode = match (mode, content_type.as_deref()) {
+        (Some(mode), _) => mode,
+        (None, Some(ct)) if ct.starts_with("application/x-www-form-urlencoded") => {
+            form = Param::parse(&body.take().unwrap_or_default());
+            BodyMode::Form
+        }
+        (None, Some(ct)) if !ct.contains("json") => BodyMode::Raw,
+        (None, _) => BodyMode::Json,
+    };
+
+    let mut request = Request::new(method, url.path(), headers, &body.unwrap_or_default());
+    request.query = query;
+    request.body_mode = mode;
+    request.form = form;
+    request.desc = format!("imported from curl {host}");
+
+    Ok(Import { host, request })
+}
+
+/// Whether the host is one of the app's ingresses, or the same service under another domain.
+pub fn matches(app: &App, host: &str) -> bool {
+    let service = |h: &str| h.split(['.', ':']).next().unwrap_or_default().to_string();
+
+    app.hosts.iter().any(|h| h == host) || (!app.hosts.is_empty() && service(host) == app.name)
+}
+
+/// Splits a command line into arguments the way a POSIX shell would, without expansions.
+fn split(command: &str) -> Result<Vec<String>> {
+    let mut args = vec![];
+    let mut arg: Option<String> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => bail!("unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => arg.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => bail!("unterminated double quote"),
+                        },
+                        Some(c) => arg.push(c),
+                        None => bail!("unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') | Some('\r') | None => {}
+                Some(c) => arg.get_or_insert_with(String::new).push(c),
+            },
+            c if c.is_whitespace() => {
+                if let Some(arg) = arg.take() {
+                    args.push(arg);
+                }
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_post_with_headers_and_body() {
+        let import = parse(
+            r#"curl -X POST 'https://utsjekk.intern.dev.nav.no/api/iverksetting?dry=true' \
+                -H 'Content-Type: application/json' \
+                -H "Accept: */*" \
+                --data-raw '{ "sakId": "1234" }'"#,
+        )
+        .unwrap();
+
+        let req = import.request;
+        assert_eq!(import.host, "utsjekk.intern.dev.nav.no");
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(req.path, "/api/iverksetting");
+        assert_eq!(req.query, vec![Param::new("dry", "true")]);
+        assert_eq!(req.body, r#"{ "sakId": "1234" }"#);
+        assert!(req
+            .headers
+            .contains(&Header::new("Accept".into(), "*/*".into())));
+    }
+
+    #[test]
+    fn parse_json_and_user() {
+        let req = parse(r#"curl --json '{"a":1}' -u user:pass localhost:8080/status"#)
+            .unwrap()
+            .request;
+
+        assert_eq!(req.method, Method::Post);
+        assert!(req.headers.contains(&Header::new(
+            "Authorization".into(),
+            "Basic dXNlcjpwYXNz".into()
+        )));
+        assert!(req.headers.contains(&Header::new(
+            "Content-Type".into(),
+            "application/json".into()
+        )));
+    }
+
+    #[test]
+    fn explicit_authorization_wins_over_user() {
+        let req = parse("curl -u user:pass -H 'Authorization: Bearer abc' localhost")
+            .unwrap()
+            .request;
+
+        assert_eq!(
+            req.headers,
+            vec![Header::new("Authorization".into(), "Bearer abc".into())]
+        );
+    }
+
+    #[test]
+    fn parse_form_modes() {
+        let req = parse("curl -F name=rapport -F 'file=@./rapport.pdf' localhost/upload")
+            .unwrap()
+            .request;
+        assert_eq!(req.body_mode, BodyMode::Multipart);
+        assert_eq!(req.form[1].file(), Some("./rapport.pdf"));
+
+        let req = parse("curl --data-urlencode 'q=a b' localhost")
+            .unwrap()
+            .request;
+        assert_eq!(req.body_mode, BodyMode::Form);
+        assert_eq!(req.form, vec![Param::new("q", "a b")]);
+
+        let req = parse("curl --data-binary @dump.bin localhost")
+            .unwrap()
+            .request;
+        assert_eq!(req.body_mode, BodyMode::File);
+        assert_eq!(req.body, "dump.bin");
+    }
+
+    #[test]
+    fn skip_values_of_other_flags() {
+        let import =
+            parse("curl -s -o out.json -A 'agent 1' --connect-timeout 5 localhost/a").unwrap();
+
+        assert_eq!(import.host, "localhost");
+        assert_eq!(import.request.path, "/a");
+    }
+
+    #[test]
+    fn split_quotes_and_continuations() {
+        let args = split("curl -H \"X-Id: \\\"1\\\"\" \\\n 'a b'").unwrap();
+
+        assert_eq!(args, vec!["curl", "-H", "X-Id: \"1\"", "a b"]);
+    }
+
+    #[test]
+    fn not_curl() {
+        assert!(parse("wget https://nav.no").is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt::Display;
+
+use crate::state::{BodyMode, Param, Request};
+
+const BOUNDARY: &str = "boundary";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Curl,
+    Httpie,
+    Http,
+}
+
+impl Format {
+    pub fn next(self) -> Self {
+        match self {
+            Format::Curl => Format::Httpie,
+            Format::Httpie => Format::Http,
+            Format::Http => Format::Curl,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            Format::Curl | Format::Httpie => "sh",
+            Format::Http => "http",
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Curl => write!(f, "curl"),
+            Format::Httpie => write!(f, "httpie"),
+            Format::Http => write!(f, "http"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Format {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "curl" => Ok(Format::Curl),
+            "httpie" => Ok(Format::Httpie),
+            "http" => Ok(Format::Http),
+            _ => Err(format!(
+                "unknown format {value}, expected curl, httpie or http"
+            )),
+        }
+    }
+}
+
+/// Renders the request as it would be sent by the client, so it can be reproduced outside the app.
+/// Without a token, the bearer token is left as a `$TOKEN` (or `{{TOKEN}}` in .http) placeholder.
+/// Disabled headers are left out and secret header values become placeholders the same way.
+/// A request with an `Authorization` header of its own is exported without the bearer token.
+pub fn export(format: Format, host: &str, req: &Request, token: Option<&str>) -> String {
+    let url = url(host, &req.path_with_query());
+    let body = req.has_body();
+    let bearer = !req.has_header("authorization");
+    let form = req.form.iter().filter(|p| p.enabled).collect::<Vec<_>>();
+
+    let secret = |key: &str| match format {
+        Format::Curl | Format::Httpie => format!("${}", placeholder(key)),
+        Format::Http => format!("{{{{{}}}}}", placeholder(key)),
+    };
+
+    let mut headers = req
+        .headers
+        .iter()
+        .filter(|h| h.enabled)
+        .map(|h| match h.secret {
+            true => (h.key.clone(), secret(&h.key), true),
+            false => (h.key.clone(), h.value.clone(), false),
+        })
+        .collect::<Vec<_>>();
+
+    if body && !req.has_header("content-type") {
+        match (req.body_mode.content_type(), format) {
+            (Some(content_type), _) => {
+                headers.push(("Content-Type".into(), content_type.into(), false))
+            }
+            (None, Format::Http) => headers.push((
+                "Content-Type".into(),
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+                false,
+            )),
+            (None, _) => {} // curl and httpie add the boundary themselves
+        }
+    }
+
+    match format {
+        Format::Curl => {
+            let auth = match token {
+                Some(token) => quote(&format!("Authorization: Bearer {token}")),
+                None => "\"Authorization: Bearer $TOKEN\"".into(),
+            };
+
+            let mut lines = vec![format!("curl -X {} {}", req.method, quote(&url))];
+            if bearer {
+                lines.push(format!("-H {auth}"));
+            }
+            // the placeholders of secrets are left for the shell to expand
+            lines.extend(headers.iter().map(|(k, v, secret)| match secret {
+                true => format!("-H \"{k}: {v}\""),
+                false => format!("-H {}", quote(&format!("{k}: {v}"))),
+            }));
+            if body {
+                lines.extend(match req.body_mode {
+                    BodyMode::Json | BodyMode::Raw => {
+                        vec![format!("--data-raw {}", quote(&req.body))]
+                    }
+                    BodyMode::Form => form
+                        .iter()
+                        .map(|p| format!("--data-urlencode {}", quote(&pair(p))))
+                        .collect(),
+                    BodyMode::Multipart => form
+                        .iter()
+                        .map(|p| match p.file() {
+                            Some(_) => format!("-F {}", quote(&pair(p))),
+                            None => format!("--form-string {}", quote(&pair(p))),
+                        })
+                        .collect(),
+                    BodyMode::File => vec![format!(
+                        "--data-binary {}",
+                        quote(&format!("@{}", req.body))
+                    )],
+                });
+            }
+            lines.join(" \\\n  ")
+        }
+        Format::Httpie => {
+            let auth = match token {
+                Some(token) => quote(&format!("Authorization:Bearer {token}")),
+                None => "\"Authorization:Bearer $TOKEN\"".into(),
+            };
+
+            let flag = match (body, req.body_mode) {
+                (true, BodyMode::Form) => "--form ",
+                (true, BodyMode::Multipart) => "--multipart ",
+                _ => "",
+            };
+            let mut lines = vec![format!("http {flag}{} {}", req.method, quote(&url))];
+            if bearer {
+                lines.push(auth);
+            }
+            lines.extend(headers.iter().map(|(k, v, secret)| match secret {
+                true => format!("\"{k}:{v}\""),
+                false => quote(&format!("{k}:{v}")),
+            }));
+            if body {
+                lines.extend(match req.body_mode {
+                    BodyMode::Json | BodyMode::Raw => vec![format!("--raw {}", quote(&req.body))],
+                    BodyMode::Form => form.iter().map(|p| quote(&pair(p))).collect(),
+                    BodyMode::Multipart => form
+                        .iter()
+                        .map(|p| match p.file() {
+                            Some(path) => quote(&format!("{}@{path}", p.key)),
+                            None => quote(&pair(p)),
+                        })
+                        .collect(),
+                    BodyMode::File => vec![quote(&format!("@{}", req.body))],
+                });
+            }
+            lines.join(" \\\n  ")
+        }
+        Format::Http => {
+            let mut lines = vec![format!("{} {url}", req.method)];
+            if bearer {
+                lines.push(format!(
+                    "Authorization: Bearer {}",
+                    token.unwrap_or("{{TOKEN}}")
+                ));
+            }
+            lines.extend(headers.iter().map(|(k, v, _)| format!("{k}: {v}")));
+            if body {
+                lines.push(String::new());
+                match req.body_mode {
+                    BodyMode::Json | BodyMode::Raw => lines.push(req.body.clone()),
+                    BodyMode::Form => lines.push(Param::encode(&req.form)),
+                    BodyMode::Multipart => {
+                        for p in &form {
+                            lines.push(format!("--{BOUNDARY}"));
+                            match p.file() {
+                                Some(path) => {
+                                    let name = path.rsplit('/').next().unwrap_or(path);
+                                    lines.push(format!(
+                                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{name}\"",
+                                        p.key
+                                    ));
+                                    lines.push(String::new());
+                                    lines.push(format!("< {path}"));
+                                }
+                                None => {
+                                    lines.push(format!(
+                                        "Content-Disposition: form-data; name=\"{}\"",
+                                        p.key
+                                    ));
+                                    lines.push(String::new());
+                                    lines.push(p.value.clone());
+                                }
+                            }
+                        }
+                        lines.push(format!("--{BOUNDARY}--"));
+                    }
+                    BodyMode::File => lines.push(format!("< {}", req.body)),
+                }
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+pub fn url(host: &str, path: &str) -> String {
+    let host = match host.starts_with("http") {
+        true => host.to_string(),
+        false => format!("https://{host}"),
+    };
+
+    match path.starts_with('/') {
+        true => format!("{host}{path}"),
+        false => format!("{host}/{path}"),
+    }
+}
+
+/// A form field as `key=value`.
+fn pair(param: &Param) -> String {
+    format!("{}={}", param.key, param.value)
+}
+
+/// The variable name standing in for a secret header, e.g. `X_API_KEY` for `X-Api-Key`.
+pub fn placeholder(key: &str) -> String {
+    key.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// Quotes a shell argument with single quotes, which disables every expansion.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{Header, Method};
+
+    use super::*;
+
+    fn request() -> Request {
+        Request::new(
+            Method::Post,
+            "/api/iverksetting",
+            vec![Header::new("Accept".into(), "application/json".into())],
+            r#"{ "navn": "O'Brien" }"#,
+        )
+    }
+
+    #[test]
+    fn curl_with_token_placeholder() {
+        let curl = export(Format::Curl, "utsjekk.intern.dev.nav.no", &request(), None);
+
+        assert_eq!(
+            curl,
+            [
+                "curl -X POST 'https://utsjekk.intern.dev.nav.no/api/iverksetting' \\",
+                "  -H \"Authorization: Bearer $TOKEN\" \\",
+                "  -H 'Accept: application/json' \\",
+                "  -H 'Content-Type: application/json' \\",
+                "  --data-raw '{ \"navn\": \"O'\\''Brien\" }'",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn curl_roundtrips_through_import() {
+        let curl = export(Format::Curl, "localhost:8080", &request(), Some("abc"));
+        let import = crate::curl::parse(&curl).unwrap();
+
+        assert_eq!(import.host, "localhost:8080");
+        assert_eq!(import.request.body, request().body);
+        assert!(import
+            .request
+            .headers
+            .contains(&Header::new("Authorization".into(), "Bearer abc".into())));
+    }
+
+    #[test]
+    fn secret_and_disabled_headers() {
+        let mut req = request();
+        req.headers.push(Header {
+            secret: true,
+            ..Header::new("X-Api-Key".into(), "hemmelig".into())
+        });
+        req.headers.push(Header {
+            enabled: false,
+            ..Header::new("X-Debug".into(), "1".into())
+        });
+        req.headers.push(Header::new("X-Price".into(), "$5".into()));
+
+        let curl = export(Format::Curl, "utsjekk", &req, None);
+        let httpie = export(Format::Httpie, "utsjekk", &req, None);
+        let http = export(Format::Http, "utsjekk", &req, None);
+
+        assert!(curl.contains("-H \"X-Api-Key: $X_API_KEY\""));
+        assert!(curl.contains("-H 'X-Price: $5'"));
+        assert!(httpie.contains("\"X-Api-Key:$X_API_KEY\""));
+        assert!(httpie.contains("'X-Price:$5'"));
+        assert!(http.contains("X-Api-Key: {{X_API_KEY}}\n"));
+        assert!(!curl.contains("hemmelig") && !http.contains("hemmelig"));
+        assert!(!curl.contains("X-Debug") && !http.contains("X-Debug"));
+    }
+
+    #[test]
+    fn own_authorization_replaces_the_bearer() {
+        let mut req = request();
+        req.headers.push(Header::new(
+            "Authorization".into(),
+            "Basic dXNlcjpwYXNz".into(),
+        ));
+
+        for format in [Format::Curl, Format::Httpie, Format::Http] {
+            let export = export(format, "utsjekk", &req, Some("abc"));
+            assert!(!export.contains("Bearer"), "{format}: {export}");
+            assert!(export.contains("Basic dXNlcjpwYXNz"));
+        }
+    }
+
+    #[test]
+    fn multipart_form() {
+        let mut req = request();
+        req.body_mode = BodyMode::Multipart;
+        req.form = vec![
+            Param::new("sakId", "1234"),
+            Param::new("fil", "@./vedlegg.pdf"),
+        ];
+
+        let curl = export(Format::Curl, "utsjekk", &req, None);
+        let http = export(Format::Http, "utsjekk", &req, None);
+
+        assert!(curl.contains("--form-string 'sakId=1234'"));
+        assert!(curl.contains("-F 'fil=@./vedlegg.pdf'"));
+        assert!(!curl.contains("Content-Type"));
+        assert!(http.contains("Content-Type: multipart/form-data; boundary=boundary\n"));
+        assert!(http.contains("filename=\"vedlegg.pdf\"\n\n< ./vedlegg.pdf\n--boundary--"));
+    }
+
+    #[test]
+    fn http_file() {
+        let http = export(Format::Http, "https://utsjekk", &request(), None);
+
+        assert!(http.starts_with("POST https://utsjekk/api/iverksetting\n"));
+        assert!(http.contains("Authorization: Bearer {{TOKEN}}\n"));
+        assert!(http.ends_with("\n\n{ \"navn\": \"O'Brien\" }"));
+    }
+}
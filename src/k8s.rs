@@ -1,20 +1,90 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::RwLock};
 
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
 use k8s_openapi::api::{
     core::v1::{EnvVar, Pod, Secret},
     networking::v1::Ingress,
 };
-use kube::{api::ListParams, Api, Client};
+use kube::{
+    api::{DeleteParams, ListParams, LogParams},
+    config::{KubeConfigOptions, Kubeconfig},
+    Api, Client, Config,
+};
 
 use crate::{
     arg::{Cli, SecretType},
     util::StringJoin,
+    NAMESPACE,
 };
 
+/// The kube context and namespace switched to with `:env` and `:ns`, the current context of the
+/// kubeconfig and [`NAMESPACE`] until then.
+struct Target {
+    context: Option<String>,
+    ns: Option<String>,
+}
+
+static TARGET: RwLock<Target> = RwLock::new(Target {
+    context: None,
+    ns: None,
+});
+
+async fn client() -> Result<Client> {
+    let context = TARGET.read().expect("k8s target lock").context.clone();
+    match context {
+        None => Ok(Client::try_default().await?),
+        Some(context) => {
+            let options = KubeConfigOptions {
+                context: Some(context),
+                ..Default::default()
+            };
+            Ok(Client::try_from(Config::from_kubeconfig(&options).await?)?)
+        }
+    }
+}
+
+/// The contexts in the kubeconfig, none if it can't be read.
+pub fn contexts() -> Vec<String> {
+    Kubeconfig::read()
+        .map(|config| config.contexts.into_iter().map(|c| c.name).collect())
+        .unwrap_or_default()
+}
+
+/// Switches to the context named like `name`, or the only one containing it, like `prod` for
+/// `prod-gcp`. Returns the name of the context.
+pub fn use_context(name: &str) -> Result<String> {
+    let contexts = contexts();
+    let matching = contexts
+        .iter()
+        .filter(|c| c.contains(name))
+        .collect::<Vec<_>>();
+    let context = match contexts.iter().find(|c| *c == name) {
+        Some(context) => context,
+        None => match matching[..] {
+            [context] => context,
+            [] => return Err(eyre!("no kube context {name}")),
+            _ => {
+                let names = matching.iter().map(|c| c.as_str()).collect::<Vec<_>>();
+                return Err(eyre!("{name} matches {}", names.join(", ")));
+            }
+        },
+    };
+    TARGET.write().expect("k8s target lock").context = Some(context.clone());
+    Ok(context.clone())
+}
+
+pub fn namespace() -> String {
+    let target = TARGET.read().expect("k8s target lock");
+    target.ns.clone().unwrap_or(NAMESPACE.into())
+}
+
+pub fn use_namespace(ns: &str) {
+    TARGET.write().expect("k8s target lock").ns = Some(ns.into());
+}
+
 
 pub async fn pod(cli: Cli) -> Result<Pod> {
-    let client = Client::try_default().await?;
+    let client = client().await?;
     let pods: Api<Pod> = Api::namespaced(client, &cli.ns);
     let params = ListParams::default().labels(&format!("app={}", cli.app_name));
     let pods = pods.list(&params).await?;
@@ -22,7 +92,7 @@ pub async fn pod(cli: Cli) -> Result<Pod> {
 }
 
 pub async fn pods(ns: &str) -> Result<Vec<Pod>> {
-    let client = Client::try_default().await?;
+    let client = client().await?;
     let pods: Api<Pod> = Api::namespaced(client, ns);
     let params = ListParams::default();
     let pods = pods.list(&params).await?;
@@ -30,7 +100,7 @@ pub async fn pods(ns: &str) -> Result<Vec<Pod>> {
 }
 
 pub async fn ings(app: &str, ns: &str) -> Result<Vec<Ingress>> {
-    let client = Client::try_default().await?;
+    let client = client().await?;
     let ings: Api<Ingress> = Api::namespaced(client, ns);
     let params = ListParams::default().labels(&format!("app={app}"));
     let ings = ings.list(&params).await?;
@@ -38,7 +108,7 @@ pub async fn ings(app: &str, ns: &str) -> Result<Vec<Ingress>> {
 }
 
 pub async fn secret(app: &str, ns: &str) -> Result<Secret> {
-    let client = Client::try_default().await?;
+    let client = client().await?;
     let secrets: Api<Secret> = Api::namespaced(client, ns);
     let app_label = format!("app={app}");
     let secret_label: String = Into::into(&SecretType::Azure);
@@ -46,23 +116,40 @@ pub async fn secret(app: &str, ns: &str) -> Result<Secret> {
     let lp = ListParams::default().labels(&label);
     let secrets = secrets.list(&lp).await?;
 
-    assert!(
-        secrets.items.len() == 1,
-        "Expected 1 secret, found {}",
-        secrets.items.len()
-    );
-
-    Ok(secrets.items.first().cloned().unwrap())
+    match secrets.items.as_slice() {
+        [secret] => Ok(secret.clone()),
+        items => Err(eyre!(
+            "expected 1 azure secret for {app} in {ns}, found {}",
+            items.len()
+        )),
+    }
 }
 
 pub async fn secrets(ns: &str) -> Result<Vec<Secret>> {
-    let client = Client::try_default().await?;
+    let client = client().await?;
     let secrets: Api<Secret> = Api::namespaced(client, ns);
     let lp = ListParams::default();
     let secrets = secrets.list(&lp).await?;
     Ok(secrets.items)
 }
 
+/// The last lines the pod logged.
+pub async fn logs(pod: &str, ns: &str) -> Result<String> {
+    let pods: Api<Pod> = Api::namespaced(client().await?, ns);
+    let params = LogParams {
+        tail_lines: Some(500),
+        ..Default::default()
+    };
+    Ok(pods.logs(pod, &params).await?)
+}
+
+/// Deletes the pod, which its deployment replaces with a new one.
+pub async fn restart(pod: &str, ns: &str) -> Result<()> {
+    let pods: Api<Pod> = Api::namespaced(client().await?, ns);
+    pods.delete(pod, &DeleteParams::default()).await?;
+    Ok(())
+}
+
 pub trait Nais {
     fn app_label(&self) -> String;
     fn app_envs(&self) -> Vec<EnvVar>;
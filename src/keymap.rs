@@ -0,0 +1,579 @@
+use std::{collections::BTreeMap, fmt::Display, sync::OnceLock};
+
+use color_eyre::eyre::{bail, eyre, Context as _, Result};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// Where a key is pressed. Text is typed as is in editors, only their commands are bound.
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Context {
+    /// Works everywhere keys aren't typed as text.
+    Global,
+    Apps,
+    Hosts,
+    Requests,
+    /// Editing a request, or typing a curl command to import.
+    Editor,
+    /// Asking for a file name or a load plan.
+    Prompt,
+    Export,
+    Runner,
+    Load,
+}
+
+impl Context {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Context::Global => "everywhere",
+            Context::Apps => "apps",
+            Context::Hosts => "hosts",
+            Context::Requests => "requests",
+            Context::Editor => "editor",
+            Context::Prompt => "prompt",
+            Context::Export => "export",
+            Context::Runner => "runner",
+            Context::Load => "load",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    Select,
+    Back,
+    Refresh,
+    Search,
+    ClearSearch,
+    MoveDown,
+    MoveUp,
+    Collapse,
+    Expand,
+    Toggle,
+    Edit,
+    New,
+    Import,
+    Export,
+    Discover,
+    Send,
+    Cancel,
+    Test,
+    SaveAs,
+    Download,
+    Load,
+    Share,
+    Duplicate,
+    Delete,
+    Close,
+    Confirm,
+    NextField,
+    ToggleEnabled,
+    ToggleSecret,
+    NextBodyMode,
+    NextFormat,
+    ToggleToken,
+    Copy,
+    Write,
+    NextTag,
+    Junit,
+    Find,
+    Command,
+    NextTheme,
+    Help,
+}
+
+impl Action {
+    /// The name in the keys file and on the command line, like `move-down`.
+    pub fn name(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => unreachable!("actions are named"),
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Action> {
+        serde_json::from_value(serde_json::Value::String(name.into())).ok()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Select => "select",
+            Action::Back => "back",
+            Action::Refresh => "refresh",
+            Action::Search => "search",
+            Action::ClearSearch => "clear search",
+            Action::MoveDown => "move down",
+            Action::MoveUp => "move up",
+            Action::Collapse => "close folder",
+            Action::Expand => "open folder",
+            Action::Toggle => "toggle folder",
+            Action::Edit => "edit",
+            Action::New => "new",
+            Action::Import => "import",
+            Action::Export => "export",
+            Action::Discover => "openapi",
+            Action::Send => "send",
+            Action::Cancel => "cancel",
+            Action::Test => "test",
+            Action::SaveAs => "save",
+            Action::Download => "download",
+            Action::Load => "load",
+            Action::Share => "publish",
+            Action::Duplicate => "copy",
+            Action::Delete => "delete",
+            Action::Close => "close",
+            Action::Confirm => "confirm",
+            Action::NextField => "next field",
+            Action::ToggleEnabled => "enable/disable",
+            Action::ToggleSecret => "secret",
+            Action::NextBodyMode => "body mode",
+            Action::NextFormat => "format",
+            Action::ToggleToken => "token",
+            Action::Copy => "copy",
+            Action::Write => "write",
+            Action::NextTag => "tag",
+            Action::Junit => "junit",
+            Action::Find => "go to",
+            Action::Command => "command",
+            Action::NextTheme => "theme",
+            Action::Help => "help",
+        }
+    }
+}
+
+/// A key with its modifiers, written like `j`, `J`, `space`, `ctrl-p` or `alt-down`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+    /// Shift is part of the char, `J` is pressed with shift but bound as `J`.
+    fn from(event: KeyEvent) -> Self {
+        let modifiers = match event.code {
+            KeyCode::Char(_) | KeyCode::BackTab => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl Key {
+    pub fn parse(text: &str) -> Result<Key> {
+        let mut rest = text;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let (modifier, after) = match rest {
+                _ if rest.len() < 2 => break,
+                _ if rest.starts_with("ctrl-") => (KeyModifiers::CONTROL, &rest[5..]),
+                _ if rest.starts_with("alt-") => (KeyModifiers::ALT, &rest[4..]),
+                _ if rest.starts_with("shift-") => (KeyModifiers::SHIFT, &rest[6..]),
+                _ => break,
+            };
+            modifiers |= modifier;
+            rest = after;
+        }
+
+        let code = match rest {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::BackTab
+            }
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => match rest.chars().collect::<Vec<_>>()[..] {
+                [c] => KeyCode::Char(c),
+                _ => bail!("unknown key {text}"),
+            },
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    Vim,
+    Emacs,
+}
+
+type Table = &'static [(Context, Action, &'static [&'static str])];
+
+const VIM: Table = &[
+    (Context::Global, Action::Find, &["ctrl-p"]),
+    (Context::Global, Action::Command, &[":"]),
+    (Context::Global, Action::NextTheme, &["ctrl-t"]),
+    (Context::Global, Action::Help, &["?"]),
+    (Context::Apps, Action::Quit, &["q"]),
+    (Context::Apps, Action::Down, &["j", "down"]),
+    (Context::Apps, Action::Up, &["k", "up"]),
+    (Context::Apps, Action::Select, &["space", "enter"]),
+    (Context::Apps, Action::Refresh, &["r"]),
+    (Context::Apps, Action::Search, &["/"]),
+    (Context::Apps, Action::ClearSearch, &["esc"]),
+    (Context::Apps, Action::Back, &["-", "backspace"]),
+    (Context::Hosts, Action::Quit, &["q"]),
+    (Context::Hosts, Action::Down, &["j", "down"]),
+    (Context::Hosts, Action::Up, &["k", "up"]),
+    (Context::Hosts, Action::Select, &["space", "enter"]),
+    (Context::Hosts, Action::Refresh, &["r"]),
+    (Context::Hosts, Action::Search, &["/"]),
+    (Context::Hosts, Action::ClearSearch, &["esc"]),
+    (Context::Hosts, Action::Back, &["-", "backspace"]),
+    (Context::Requests, Action::Quit, &["q"]),
+    (Context::Requests, Action::New, &["n"]),
+    (Context::Requests, Action::Edit, &["e"]),
+    (Context::Requests, Action::Import, &["i"]),
+    (Context::Requests, Action::Export, &["x"]),
+    (Context::Requests, Action::Discover, &["o"]),
+    (Context::Requests, Action::Send, &["s"]),
+    (Context::Requests, Action::Cancel, &["c"]),
+    (Context::Requests, Action::Test, &["t"]),
+    (Context::Requests, Action::SaveAs, &["w"]),
+    (Context::Requests, Action::Download, &["d"]),
+    (Context::Requests, Action::Load, &["L"]),
+    (Context::Requests, Action::Share, &["p"]),
+    (Context::Requests, Action::Duplicate, &["y"]),
+    (Context::Requests, Action::Delete, &["D"]),
+    (Context::Requests, Action::Search, &["/"]),
+    (Context::Requests, Action::ClearSearch, &["esc"]),
+    (Context::Requests, Action::Down, &["j", "down"]),
+    (Context::Requests, Action::Up, &["k", "up"]),
+    (Context::Requests, Action::MoveDown, &["J"]),
+    (Context::Requests, Action::MoveUp, &["K"]),
+    (Context::Requests, Action::Collapse, &["h", "left"]),
+    (Context::Requests, Action::Expand, &["l", "right"]),
+    (Context::Requests, Action::Toggle, &["space", "enter"]),
+    (Context::Requests, Action::Back, &["-", "backspace"]),
+    (Context::Editor, Action::Close, &["esc"]),
+    (Context::Editor, Action::NextField, &["tab"]),
+    (Context::Editor, Action::ToggleEnabled, &["ctrl-t"]),
+    (Context::Editor, Action::ToggleSecret, &["ctrl-s"]),
+    (Context::Editor, Action::NextBodyMode, &["ctrl-b"]),
+    (Context::Prompt, Action::Confirm, &["enter"]),
+    (Context::Prompt, Action::Close, &["esc"]),
+    (Context::Export, Action::NextFormat, &["tab"]),
+    (Context::Export, Action::ToggleToken, &["T"]),
+    (Context::Export, Action::Copy, &["y"]),
+    (Context::Export, Action::Write, &["w"]),
+    (Context::Export, Action::Close, &["esc", "q"]),
+    (Context::Runner, Action::Quit, &["q"]),
+    (Context::Runner, Action::Down, &["j", "down"]),
+    (Context::Runner, Action::Up, &["k", "up"]),
+    (Context::Runner, Action::Refresh, &["r"]),
+    (Context::Runner, Action::NextTag, &["t"]),
+    (Context::Runner, Action::Junit, &["x"]),
+    (Context::Runner, Action::Back, &["-", "backspace"]),
+    (Context::Load, Action::Quit, &["q"]),
+    (Context::Load, Action::Cancel, &["c"]),
+    (Context::Load, Action::Refresh, &["r"]),
+    (Context::Load, Action::Back, &["-", "backspace"]),
+];
+
+/// Emacs keys for moving around, on top of the vim preset's letters for the actions.
+const EMACS: Table = &[
+    (Context::Global, Action::Find, &["alt-g"]),
+    (Context::Global, Action::Command, &["alt-x", ":"]),
+    (Context::Global, Action::Help, &["ctrl-h", "?"]),
+    (Context::Apps, Action::Quit, &["ctrl-c"]),
+    (Context::Apps, Action::Down, &["ctrl-n", "down"]),
+    (Context::Apps, Action::Up, &["ctrl-p", "up"]),
+    (Context::Apps, Action::Search, &["ctrl-s"]),
+    (Context::Apps, Action::ClearSearch, &["ctrl-g", "esc"]),
+    (Context::Apps, Action::Back, &["alt-b", "backspace"]),
+    (Context::Hosts, Action::Quit, &["ctrl-c"]),
+    (Context::Hosts, Action::Down, &["ctrl-n", "down"]),
+    (Context::Hosts, Action::Up, &["ctrl-p", "up"]),
+    (Context::Hosts, Action::Search, &["ctrl-s"]),
+    (Context::Hosts, Action::ClearSearch, &["ctrl-g", "esc"]),
+    (Context::Hosts, Action::Back, &["alt-b", "backspace"]),
+    (Context::Requests, Action::Quit, &["ctrl-c"]),
+    (Context::Requests, Action::Search, &["ctrl-s"]),
+    (Context::Requests, Action::ClearSearch, &["ctrl-g", "esc"]),
+    (Context::Requests, Action::Down, &["ctrl-n", "down"]),
+    (Context::Requests, Action::Up, &["ctrl-p", "up"]),
+    (Context::Requests, Action::MoveDown, &["alt-n"]),
+    (Context::Requests, Action::MoveUp, &["alt-p"]),
+    (Context::Requests, Action::Collapse, &["ctrl-b", "left"]),
+    (Context::Requests, Action::Expand, &["ctrl-f", "right"]),
+    (Context::Requests, Action::Back, &["alt-b", "backspace"]),
+    (Context::Editor, Action::Close, &["ctrl-g", "esc"]),
+    (Context::Prompt, Action::Close, &["ctrl-g", "esc"]),
+    (Context::Export, Action::Close, &["ctrl-g", "esc"]),
+    (Context::Runner, Action::Quit, &["ctrl-c"]),
+    (Context::Runner, Action::Down, &["ctrl-n", "down"]),
+    (Context::Runner, Action::Up, &["ctrl-p", "up"]),
+    (Context::Runner, Action::Back, &["alt-b", "backspace"]),
+    (Context::Load, Action::Quit, &["ctrl-c"]),
+    (Context::Load, Action::Back, &["alt-b", "backspace"]),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Binding {
+    context: Context,
+    action: Action,
+    keys: Vec<Key>,
+}
+
+/// The keys of every action in every context, in the order they are listed in the help.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+/// `$XDG_CONFIG_HOME/helved/keys.json`, a preset and the keys of actions per context, like
+/// `{ "preset": "emacs", "requests": { "send": ["ctrl-s", "s"] } }`.
+#[derive(Deserialize, Default)]
+struct KeysFile {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(flatten)]
+    contexts: BTreeMap<Context, BTreeMap<Action, Vec<String>>>,
+}
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+/// The keymap in use, the vim preset until one is loaded.
+pub fn current() -> &'static Keymap {
+    KEYMAP.get_or_init(|| Keymap::preset(Preset::Vim))
+}
+
+pub fn init(keymap: Keymap) {
+    let _ = KEYMAP.set(keymap);
+}
+
+impl Keymap {
+    pub fn preset(preset: Preset) -> Self {
+        let parse = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| Key::parse(key).expect("valid preset key"))
+                .collect::<Vec<_>>()
+        };
+
+        let mut keymap = Keymap {
+            bindings: VIM
+                .iter()
+                .map(|(context, action, keys)| Binding {
+                    context: *context,
+                    action: *action,
+                    keys: parse(keys),
+                })
+                .collect(),
+        };
+        if preset == Preset::Emacs {
+            for (context, action, keys) in EMACS {
+                keymap.bind(*context, *action, parse(keys));
+            }
+        }
+        keymap
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = config::config_dir().join("keys.json");
+        match std::fs::read_to_string(&path) {
+            Ok(json) => Self::parse(&json).wrap_err_with(|| format!("invalid {}", path.display())),
+            Err(_) => Ok(Self::preset(Preset::Vim)),
+        }
+    }
+
+    fn parse(json: &str) -> Result<Self> {
+        let file = serde_json::from_str::<KeysFile>(json)?;
+        let mut keymap = Self::preset(file.preset);
+        for (context, actions) in file.contexts {
+            for (action, keys) in actions {
+                if !keymap
+                    .bindings
+                    .iter()
+                    .any(|b| b.context == context && b.action == action)
+                {
+                    return Err(eyre!(
+                        "{} has no action {}",
+                        context.title(),
+                        action.label()
+                    ));
+                }
+                let keys = keys.iter().map(|k| Key::parse(k)).collect::<Result<_>>()?;
+                keymap.bind(context, action, keys);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Binds the keys to the action, taking them from other actions in the context.
+    fn bind(&mut self, context: Context, action: Action, keys: Vec<Key>) {
+        for binding in self.bindings.iter_mut().filter(|b| b.context == context) {
+            binding.keys.retain(|key| !keys.contains(key));
+        }
+        if let Some(binding) = self
+            .bindings
+            .iter_mut()
+            .find(|b| b.context == context && b.action == action)
+        {
+            binding.keys = keys;
+        }
+    }
+
+    pub fn action(&self, context: Context, event: KeyEvent) -> Option<Action> {
+        let key = Key::from(event);
+        self.bindings
+            .iter()
+            .find(|b| b.context == context && b.keys.contains(&key))
+            .map(|b| b.action)
+    }
+
+    /// The actions of the context with their keys, for the help.
+    pub fn actions(&self, context: Context) -> Vec<(Action, Vec<Key>)> {
+        self.bindings
+            .iter()
+            .filter(|b| b.context == context && !b.keys.is_empty())
+            .map(|b| (b.action, b.keys.clone()))
+            .collect()
+    }
+
+    /// The first key of the action like `(ctrl-t)`, to mention it in titles and messages.
+    pub fn hint(&self, context: Context, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|b| b.context == context && b.action == action)
+            .and_then(|b| b.keys.first())
+            .map(|key| format!("({key})"))
+            .unwrap_or_default()
+    }
+
+    /// Every action of the context and the global ones, with their first key.
+    pub fn footer(&self, context: Context) -> String {
+        self.actions(context)
+            .into_iter()
+            .chain(self.actions(Context::Global))
+            .map(|(action, keys)| format!("({}) {}", keys[0], action.label()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parse_and_display_keys() {
+        for key in [
+            "j",
+            "J",
+            "-",
+            "space",
+            "ctrl-p",
+            "alt-down",
+            "shift-tab",
+            "?",
+        ] {
+            assert_eq!(Key::parse(key).unwrap().to_string(), key);
+        }
+        assert!(Key::parse("ctrl-").is_err());
+        assert!(Key::parse("hyper-x").is_err());
+    }
+
+    #[test]
+    fn shifted_chars_match_their_binding() {
+        let keymap = Keymap::preset(Preset::Vim);
+        let shift_j = event(KeyCode::Char('J'), KeyModifiers::SHIFT);
+
+        assert_eq!(
+            keymap.action(Context::Requests, shift_j),
+            Some(Action::MoveDown)
+        );
+        assert_eq!(
+            keymap.action(
+                Context::Global,
+                event(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            ),
+            Some(Action::Find)
+        );
+    }
+
+    #[test]
+    fn user_keys_replace_the_preset() {
+        let json = r#"{ "preset": "emacs", "requests": { "send": ["ctrl-s", "s"] } }"#;
+        let keymap = Keymap::parse(json).unwrap();
+        let ctrl_s = event(KeyCode::Char('s'), KeyModifiers::CONTROL);
+
+        // emacs binds ctrl-s to search, the user's binding takes it
+        assert_eq!(keymap.action(Context::Requests, ctrl_s), Some(Action::Send));
+        assert_eq!(keymap.hint(Context::Requests, Action::Search), "");
+        assert_eq!(keymap.hint(Context::Requests, Action::Down), "(ctrl-n)");
+        assert!(Keymap::parse(r#"{ "apps": { "send": ["s"] } }"#).is_err());
+    }
+
+    #[test]
+    fn footer_lists_bound_actions() {
+        let footer = Keymap::preset(Preset::Vim).footer(Context::Load);
+
+        assert_eq!(
+            footer,
+            "(q) quit (c) cancel (r) refresh (-) back (ctrl-p) go to (:) command (ctrl-t) theme (?) help"
+        );
+    }
+}
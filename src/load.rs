@@ -0,0 +1,325 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future::join_all;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Client,
+    state::Request,
+    util::{millis, number},
+};
+
+/// How many requests to send, how many to have in flight at once and, optionally, how fast.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    pub stop: Stop,
+    pub concurrency: usize,
+    /// Requests per second across all workers, as fast as possible when unset.
+    pub rps: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// Sends this many requests.
+    Count(usize),
+    /// Keeps sending until this much time has passed.
+    Time(Duration),
+}
+
+impl Default for Plan {
+    fn default() -> Self {
+        Self {
+            stop: Stop::Count(100),
+            concurrency: 10,
+            rps: None,
+        }
+    }
+}
+
+impl Plan {
+    /// Reads a plan written like `n=1000 c=10` or `t=30s c=5 rps=50`.
+    pub fn parse(line: &str) -> Result<Plan, String> {
+        let mut plan = Plan::default();
+        for pair in line.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got {pair}"))?;
+            match key {
+                "n" => plan.stop = Stop::Count(number(value)?),
+                "t" => plan.stop = Stop::Time(Duration::from_millis(millis(value)?)),
+                "c" => plan.concurrency = number(value)?,
+                "rps" => plan.rps = Some(number(value)?),
+                _ => return Err(format!("unknown option {key}")),
+            }
+        }
+
+        match plan {
+            Plan { concurrency: 0, .. } => Err("c must be at least 1".into()),
+            Plan { rps: Some(0), .. } => Err("rps must be at least 1".into()),
+            plan => Ok(plan),
+        }
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.stop {
+            Stop::Count(n) => write!(f, "n={n}")?,
+            Stop::Time(t) if t.subsec_millis() == 0 => write!(f, "t={}s", t.as_secs())?,
+            Stop::Time(t) => write!(f, "t={}ms", t.as_millis())?,
+        }
+        write!(f, " c={}", self.concurrency)?;
+        if let Some(rps) = self.rps {
+            write!(f, " rps={rps}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A response, or a failure to get one, timed from the start of the run.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub at: Duration,
+    pub latency: Duration,
+    /// None when the request failed without a response.
+    pub status: Option<u16>,
+}
+
+/// Shared with the UI while the load runs in the background.
+#[derive(Default, Debug)]
+pub struct Stats {
+    pub hits: Vec<Hit>,
+    pub last_error: Option<String>,
+    pub elapsed: Duration,
+    pub done: bool,
+}
+
+impl Stats {
+    pub fn errors(&self) -> usize {
+        self.hits.iter().filter(|h| h.status.is_none()).count()
+    }
+
+    /// The number of responses per status code.
+    pub fn statuses(&self) -> BTreeMap<u16, usize> {
+        let mut statuses = BTreeMap::new();
+        for status in self.hits.iter().filter_map(|h| h.status) {
+            *statuses.entry(status).or_default() += 1;
+        }
+        statuses
+    }
+
+    /// The latencies in milliseconds of the hits that got a response, sorted.
+    pub fn latencies(&self) -> Vec<u64> {
+        self.hits
+            .iter()
+            .filter(|h| h.status.is_some())
+            .map(|h| h.latency.as_millis() as u64)
+            .sorted()
+            .collect()
+    }
+
+    /// The p50, p90 and p99 latencies for each second of the run.
+    pub fn timeline(&self) -> Vec<(u64, [u64; 3])> {
+        let mut seconds = BTreeMap::<u64, Vec<u64>>::new();
+        for hit in self.hits.iter().filter(|h| h.status.is_some()) {
+            seconds
+                .entry(hit.at.as_secs())
+                .or_default()
+                .push(hit.latency.as_millis() as u64);
+        }
+
+        seconds
+            .into_iter()
+            .map(|(second, mut latencies)| {
+                latencies.sort();
+                let p = |p| percentile(&latencies, p);
+                (second, [p(50), p(90), p(99)])
+            })
+            .collect()
+    }
+
+    pub fn rps(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.hits.len() as f64 / secs,
+        }
+    }
+
+    pub fn summary(&self, plan: &Plan) -> Summary {
+        let latencies = self.latencies();
+        Summary {
+            plan: plan.to_string(),
+            requests: self.hits.len(),
+            errors: self.errors(),
+            duration_ms: self.elapsed.as_millis() as u64,
+            p50: percentile(&latencies, 50),
+            p90: percentile(&latencies, 90),
+            p99: percentile(&latencies, 99),
+            statuses: self.statuses(),
+        }
+    }
+}
+
+/// The outcome of a load run, kept in the history of the request.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Summary {
+    pub plan: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub duration_ms: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub statuses: BTreeMap<u16, usize>,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.duration_ms as f64 / 1000.0;
+        let rps = match secs {
+            0.0 => 0.0,
+            secs => self.requests as f64 / secs,
+        };
+        write!(
+            f,
+            "{} requests in {secs:.1}s ({rps:.1} rps), {} errors, p50 {} ms p90 {} ms p99 {} ms",
+            self.requests, self.errors, self.p50, self.p90, self.p99
+        )
+    }
+}
+
+/// The latency at the percentile of the sorted latencies, by the nearest-rank method.
+pub fn percentile(sorted: &[u64], p: usize) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[(p * len).div_ceil(100).clamp(1, len) - 1],
+    }
+}
+
+/// Sends the request according to the plan, recording every hit in the stats as it arrives.
+/// Aborting the task stops all workers.
+pub async fn run(client: Client, request: Request, plan: Plan, stats: Arc<Mutex<Stats>>) {
+    let start = Instant::now();
+    let sent = AtomicUsize::new(0);
+
+    let workers =
+        (0..plan.concurrency).map(|_| work(&client, &request, &plan, &stats, &sent, start));
+    join_all(workers).await;
+
+    let mut stats = stats.lock().expect("load stats");
+    stats.elapsed = start.elapsed();
+    stats.done = true;
+}
+
+/// Sends requests one after the other until the plan is done. With a target rate, the n-th
+/// request across all workers is sent no earlier than n / rps seconds after the start.
+async fn work(
+    client: &Client,
+    request: &Request,
+    plan: &Plan,
+    stats: &Mutex<Stats>,
+    sent: &AtomicUsize,
+    start: Instant,
+) {
+    loop {
+        let n = sent.fetch_add(1, Ordering::Relaxed);
+        if let Stop::Count(count) = plan.stop {
+            if n >= count {
+                return;
+            }
+        }
+
+        if let Some(rps) = plan.rps {
+            let due = Duration::from_secs_f64(n as f64 / rps as f64);
+            tokio::time::sleep(due.saturating_sub(start.elapsed())).await;
+        }
+
+        if let Stop::Time(time) = plan.stop {
+            if start.elapsed() >= time {
+                return;
+            }
+        }
+
+        let sent_at = Instant::now();
+        let result = client.hit(request).await;
+        let hit = Hit {
+            at: start.elapsed(),
+            latency: sent_at.elapsed(),
+            status: result.as_ref().ok().copied(),
+        };
+
+        let mut stats = stats.lock().expect("load stats");
+        stats.hits.push(hit);
+        stats.elapsed = start.elapsed();
+        if let Err(err) = result {
+            stats.last_error = Some(format!("{err:#}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plan() {
+        let plan = Plan::parse("t=30s c=5 rps=50").unwrap();
+
+        assert_eq!(plan.stop, Stop::Time(Duration::from_secs(30)));
+        assert_eq!(plan.concurrency, 5);
+        assert_eq!(plan.rps, Some(50));
+        assert_eq!(plan.to_string(), "t=30s c=5 rps=50");
+        assert_eq!(Plan::parse("n=1000").unwrap().to_string(), "n=1000 c=10");
+        assert!(Plan::parse("c=0").is_err());
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let latencies = (1..=200).collect::<Vec<u64>>();
+
+        assert_eq!(percentile(&latencies, 50), 100);
+        assert_eq!(percentile(&latencies, 90), 180);
+        assert_eq!(percentile(&latencies, 99), 198);
+        assert_eq!(percentile(&[7], 99), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn summary_counts_statuses_and_errors() {
+        let hit = |ms, status| Hit {
+            at: Duration::from_millis(ms),
+            latency: Duration::from_millis(ms),
+            status,
+        };
+        let stats = Stats {
+            hits: vec![
+                hit(10, Some(200)),
+                hit(30, Some(503)),
+                hit(20, Some(200)),
+                hit(5, None),
+            ],
+            last_error: None,
+            elapsed: Duration::from_secs(2),
+            done: true,
+        };
+
+        let summary = stats.summary(&Plan::default());
+        assert_eq!(summary.requests, 4);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.p50, 20);
+        assert_eq!(summary.statuses, BTreeMap::from([(200, 2), (503, 1)]));
+        assert_eq!(
+            summary.to_string(),
+            "4 requests in 2.0s (2.0 rps), 1 errors, p50 20 ms p90 30 ms p99 30 ms"
+        );
+    }
+}
@@ -1,32 +1,87 @@
-use std::panic;
+use std::{panic, time::Duration};
 
-use arg::ArgMethod;
-use color_eyre::eyre::{self, Context};
+use arg::{ArgMethod, Command};
+use color_eyre::eyre::{self, eyre, Context as _};
+use keymap::{Action, Context};
 use ratatui::{
     backend::Backend,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     Terminal,
 };
-use state::State;
+use state::{Settings, State};
 use tui::{View, TUI};
 use views::requests::{Direction, Editing, RequestMode};
+use views::search::Search;
 
 pub mod arg;
 pub mod client;
+pub mod command;
+pub mod config;
+pub mod curl;
+pub mod export;
 pub mod k8s;
+pub mod keymap;
+pub mod load;
+pub mod merge;
+pub mod migrate;
 pub mod oauth2;
+pub mod openapi;
+pub mod postman;
+pub mod runner;
 pub mod state;
+pub mod theme;
 pub mod tui;
 pub mod util;
+pub mod vars;
 pub mod views;
+pub mod workspace;
 
 pub const NAMESPACE: &str = "helved";
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
+    match arg::command() {
+        Command::Run { app, tag, junit } => {
+            color_eyre::install()?;
+            return run_requests(&app, tag.as_deref(), junit.as_deref());
+        }
+        Command::ImportCurl { command, app } => {
+            color_eyre::install()?;
+            return import_curl(&command, app.as_deref());
+        }
+        Command::Export {
+            app,
+            request,
+            format,
+            token,
+        } => {
+            color_eyre::install()?;
+            return export_request(&app, &request, format, token);
+        }
+        Command::ImportPostman { app, file } => {
+            color_eyre::install()?;
+            return import_postman(&app, &file);
+        }
+        Command::ExportPostman { app, file } => {
+            color_eyre::install()?;
+            return export_postman(&app, file.as_deref());
+        }
+        Command::OpenApi { app, host, file } => {
+            color_eyre::install()?;
+            return discover(&app, host.as_deref(), file.as_deref());
+        }
+        Command::Settings { app, settings } => {
+            color_eyre::install()?;
+            return app_settings(&app, settings.as_deref());
+        }
+        Command::Tui => {}
+    }
+
     install_hooks()?;
+    let state = State::load()?;
+    keymap::init(keymap::Keymap::load()?);
+    theme::init(&config::Config::load()?)?;
     let terminal = tui::init()?;
-    let state = State::load();
     let tui = TUI::new(state);
     run(tui, terminal).wrap_err("run failed")?;
 
@@ -47,66 +102,323 @@ impl From<arg::ArgMethod> for state::Method {
     }
 }
 
+fn run_requests(app: &str, tag: Option<&str>, junit: Option<&str>) -> color_eyre::Result<()> {
+    let mut state = State::load()?;
+    let mut app = state
+        .get(app)
+        .cloned()
+        .ok_or_else(|| eyre!("unknown app {app}"))?;
+
+    let report = runner::run(&mut app, tag);
+    report.print();
+
+    if let Some(path) = junit {
+        std::fs::write(path, report.junit()).wrap_err("write junit report")?;
+    }
+
+    state.insert(app);
+    save(&mut state);
+
+    if report.failures() > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn import_curl(command: &str, app: Option<&str>) -> color_eyre::Result<()> {
+    let import = curl::parse(command)?;
+    let mut state = State::load()?;
+
+    let name = match app {
+        Some(app) => app.to_string(),
+        None => state
+            .values()
+            .into_iter()
+            .find(|app| curl::matches(app, &import.host))
+            .map(|app| app.name.clone())
+            .ok_or_else(|| eyre!("no app with host {}, use -app <name>", import.host))?,
+    };
+
+    let app = state
+        .get_mut(&name)
+        .ok_or_else(|| eyre!("unknown app {name}"))?;
+
+    if !curl::matches(app, &import.host) {
+        println!("warning: {} is not a host of {name}", import.host);
+    }
+
+    println!(
+        "imported {} {} into {name}",
+        import.request.method, import.request.path
+    );
+    app.add_request(import.request);
+    save(&mut state);
+
+    Ok(())
+}
+
+fn export_request(
+    app: &str,
+    request: &str,
+    format: export::Format,
+    token: bool,
+) -> color_eyre::Result<()> {
+    let state = State::load()?;
+    let mut app = state
+        .get(app)
+        .cloned()
+        .ok_or_else(|| eyre!("unknown app {app}"))?;
+
+    let req = app
+        .requests
+        .iter()
+        .find(|r| r.id == request || r.path == request)
+        .map(|r| vars::resolve(r, &app.vars))
+        .ok_or_else(|| eyre!("no request {request} in {}", app.name))?;
+
+    let host = app
+        .host()
+        .map(String::from)
+        .ok_or_else(|| eyre!("{} has no hosts", app.name))?;
+
+    let token = match token {
+        true => Some(app.token()?),
+        false => None,
+    };
+
+    println!("{}", export::export(format, &host, &req, token.as_deref()));
+    Ok(())
+}
+
+fn import_postman(app: &str, file: &str) -> color_eyre::Result<()> {
+    let json = std::fs::read_to_string(file).wrap_err_with(|| format!("read {file}"))?;
+    let import = postman::import(&json)?;
+    let mut state = State::load()?;
+    let app = state
+        .get_mut(app)
+        .ok_or_else(|| eyre!("unknown app {app}"))?;
+
+    for skipped in &import.skipped {
+        println!("skipped {skipped}");
+    }
+
+    println!(
+        "imported {} requests and {} variables into {}",
+        import.requests.len(),
+        import.vars.len(),
+        app.name
+    );
+
+    import.requests.into_iter().for_each(|r| app.add_request(r));
+    app.vars.extend(import.vars);
+    save(&mut state);
+
+    Ok(())
+}
+
+fn export_postman(app: &str, file: Option<&str>) -> color_eyre::Result<()> {
+    let state = State::load()?;
+    let app = state.get(app).ok_or_else(|| eyre!("unknown app {app}"))?;
+    let json = postman::export(app)?;
+
+    match file {
+        Some(file) => std::fs::write(file, json).wrap_err_with(|| format!("write {file}"))?,
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+fn discover(app: &str, host: Option<&str>, file: Option<&str>) -> color_eyre::Result<()> {
+    let mut state = State::load()?;
+    let app = state
+        .get_mut(app)
+        .ok_or_else(|| eyre!("unknown app {app}"))?;
+
+    let summary = match file {
+        Some(file) => {
+            let json = std::fs::read_to_string(file).wrap_err_with(|| format!("read {file}"))?;
+            openapi::store(app, serde_json::from_str(&json)?)?
+        }
+        None => openapi::discover(app, host)?,
+    };
+    println!("{}: {summary}", app.name);
+    save(&mut state);
+
+    Ok(())
+}
+
+fn app_settings(app: &str, settings: Option<&str>) -> color_eyre::Result<()> {
+    let mut state = State::load()?;
+    let app = state
+        .get_mut(app)
+        .ok_or_else(|| eyre!("unknown app {app}"))?;
+
+    if let Some(settings) = settings {
+        app.settings = Settings::parse(settings).map_err(|err| eyre!(err))?;
+    }
+    println!("{}: {}", app.name, app.settings);
+    save(&mut state);
+
+    Ok(())
+}
+
+/// Saves the state of a command, with a warning for what couldn't be written.
+fn save(state: &mut State) {
+    state.save();
+    for problem in state.problems() {
+        println!("warning: {problem}");
+    }
+}
+
 #[allow(dead_code)]
 fn run(mut tui: tui::TUI, mut term: Terminal<impl Backend>) -> color_eyre::Result<()> {
     // let mut last_key: KeyCode = KeyCode::Null;
+    let keys = keymap::current();
 
     loop {
-        term.draw(|frame| {
-            match &mut tui.view {
-                View::Apps(view) => view.render(frame),
-                View::Ingresses(view) => view.render(frame),
-                View::Requests(view) => view.render(frame),
-            };
-        })?;
-
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match &mut tui.view {
-                    View::Apps(view) => match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('j') | KeyCode::Down => view.down(),
-                        KeyCode::Char('k') | KeyCode::Up => view.up(),
-                        KeyCode::Char(' ') | KeyCode::Enter => tui.enter(),
-                        KeyCode::Char('-') | KeyCode::Backspace => tui.back(),
-                        KeyCode::Char('r') => tui.refresh(),
-                        _ => {}
-                    },
-                    View::Ingresses(view) => match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('j') | KeyCode::Down => view.down(),
-                        KeyCode::Char('k') | KeyCode::Up => view.up(),
-                        KeyCode::Char(' ') | KeyCode::Enter => tui.enter(),
-                        KeyCode::Char('-') | KeyCode::Backspace => tui.back(),
-                        KeyCode::Char('r') => tui.refresh(),
-                        _ => {}
-                    },
-                    View::Requests(view) => match &mut view.mode {
-                        RequestMode::Normal => match key.code {
-                            KeyCode::Char('q') => break,
-                            KeyCode::Char('j') | KeyCode::Down => view.down(),
-                            KeyCode::Char('k') | KeyCode::Up => view.up(),
-                            KeyCode::Char('-') | KeyCode::Backspace => tui.back(),
-                            KeyCode::Char('e') => view.edit(),
-                            KeyCode::Char('n') => view.new_request(),
-                            _ => {},
-                        },
-                        RequestMode::Insert(edit, _) => match key.code {
-                            KeyCode::Esc => view.save(&mut tui.state),
-                            KeyCode::Tab => view.next_edit(&mut tui.state),
-                            KeyCode::Left => edit.move_cursor(Direction::Left, 1),
-                            KeyCode::Right => edit.move_cursor(Direction::Right, 1),
-                            KeyCode::Up => edit.move_cursor(Direction::Up, 1),
-                            KeyCode::Down => edit.move_cursor(Direction::Down, 1),
-                            KeyCode::Char(n) => edit.add_char(n),
-                            KeyCode::Enter => edit.new_line(),
-                            KeyCode::Backspace => edit.del_char(),
-                            KeyCode::End => edit.move_cursor(Direction::Right, usize::MAX),
-                            KeyCode::Home => edit.move_cursor(Direction::Left, usize::MAX),
-                            _ => {},
+        match &mut tui.view {
+            View::Requests(view) => view.poll(&mut tui.state),
+            View::Load(view) => view.poll(&mut tui.state),
+            _ => {}
+        }
+        tui.sync();
+
+        term.draw(|frame| tui::layout(&mut tui, frame))?;
+
+        // redraw now and then, so background requests, downloads and load runs can show their progress
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let event = event::read()?;
+        if let Event::Paste(text) = &event {
+            if let Some(line) = &mut tui.command {
+                line.paste(text);
+                continue;
+            }
+            if let View::Requests(view) = &mut tui.view {
+                match &mut view.mode {
+                    RequestMode::Insert(_, field) if field.is_grid() => view.grid.paste(text),
+                    RequestMode::Insert(edit, _)
+                    | RequestMode::Import(edit)
+                    | RequestMode::Save(edit, _)
+                    | RequestMode::Load(edit) => edit.paste(text),
+                    RequestMode::Normal | RequestMode::Export(_, _) => {}
+                }
+            }
+        }
+
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        tui.status = None;
+        if tui.help {
+            tui.help = false;
+            continue;
+        }
+        if let Some(finder) = &mut tui.finder {
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => tui.finder = None,
+                KeyCode::Enter => tui.jump(),
+                KeyCode::Char('n') if ctrl => finder.down(),
+                KeyCode::Char('p') if ctrl => finder.up(),
+                KeyCode::Down => finder.down(),
+                KeyCode::Up => finder.up(),
+                KeyCode::Backspace => finder.del_char(),
+                KeyCode::Char(c) => finder.add_char(c),
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(line) = &mut tui.command {
+            match key.code {
+                KeyCode::Esc => tui.command = None,
+                KeyCode::Backspace if line.input.is_empty() => tui.command = None,
+                KeyCode::Backspace => line.del_char(),
+                KeyCode::Tab => line.next(),
+                KeyCode::Char(c) => line.add_char(c),
+                KeyCode::Enter => {
+                    let context = line.context();
+                    let input = std::mem::take(&mut line.input);
+                    tui.command = None;
+                    match command::parse(&input, context) {
+                        Ok(command) => {
+                            if !execute(&mut tui, command) {
+                                break;
+                            }
                         }
-                        
-                    },
+                        Err(err) => tui.status = Some(format!("{err}")),
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(logs) = &mut tui.logs {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => logs.down(),
+                KeyCode::Char('k') | KeyCode::Up => logs.up(),
+                KeyCode::Esc | KeyCode::Char('q') => tui.logs = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        let typing = match &tui.view {
+            View::Apps(view) => view.search.typing,
+            View::Ingresses(view) => view.search.typing,
+            View::Requests(view) => view.search.typing || view.mode != RequestMode::Normal,
+            View::Runner(_) | View::Load(_) => false,
+        };
+        if !typing {
+            if let Some(action) = keys.action(Context::Global, key) {
+                if !perform(&mut tui, action) {
+                    break;
+                }
+                continue;
+            }
+        }
+
+        let action = keys.action(tui.context(), key);
+        match &mut tui.view {
+            View::Apps(view) if view.search.typing => {
+                if search_key(&mut view.search, key.code) {
+                    view.searched();
+                }
+            }
+            View::Ingresses(view) if view.search.typing => {
+                if search_key(&mut view.search, key.code) {
+                    view.searched();
+                }
+            }
+            View::Requests(view) if view.mode == RequestMode::Normal && view.search.typing => {
+                if search_key(&mut view.search, key.code) {
+                    view.searched();
+                }
+            }
+            // text is typed in editors and prompts, their commands are bound
+            View::Requests(view) if action.is_none() => match &mut view.mode {
+                RequestMode::Insert(_, field) if field.is_grid() => {
+                    edit_key(&mut view.grid, key.code, true)
+                }
+                RequestMode::Insert(edit, _) | RequestMode::Import(edit) => {
+                    edit_key(edit, key.code, true)
+                }
+                RequestMode::Save(edit, _) | RequestMode::Load(edit) => {
+                    edit_key(edit, key.code, false)
+                }
+                RequestMode::Normal | RequestMode::Export(_, _) => {}
+            },
+            _ => {
+                if let Some(action) = action {
+                    if !perform(&mut tui, action) {
+                        break;
+                    }
                 }
             }
         }
@@ -114,6 +426,171 @@ fn run(mut tui: tui::TUI, mut term: Terminal<impl Backend>) -> color_eyre::Resul
     Ok(())
 }
 
+/// Does what the action does in the view, returns false to quit.
+fn perform(tui: &mut TUI, action: Action) -> bool {
+    match action {
+        Action::Quit => return false,
+        Action::Find => tui.find(),
+        Action::Command => tui.open_command(),
+        Action::NextTheme => tui.status = Some(format!("theme {}", theme::next())),
+        Action::Help => tui.help = true,
+        Action::Back => tui.back(),
+        Action::Refresh => tui.refresh(),
+        Action::Test => tui.run_requests(),
+        _ => {}
+    }
+
+    match &mut tui.view {
+        View::Apps(view) => match action {
+            Action::Down => view.down(),
+            Action::Up => view.up(),
+            Action::Select => tui.enter(),
+            Action::Search => view.search.start(),
+            Action::ClearSearch => {
+                view.search.clear();
+                view.searched();
+            }
+            _ => {}
+        },
+        View::Ingresses(view) => match action {
+            Action::Down => view.down(),
+            Action::Up => view.up(),
+            Action::Select => tui.enter(),
+            Action::Search => view.search.start(),
+            Action::ClearSearch => {
+                view.search.clear();
+                view.searched();
+            }
+            _ => {}
+        },
+        View::Requests(view) => match (&view.mode, action) {
+            (RequestMode::Normal, Action::Down) => view.down(),
+            (RequestMode::Normal, Action::Up) => view.up(),
+            (RequestMode::Normal, Action::MoveDown) => view.move_selected(1, &mut tui.state),
+            (RequestMode::Normal, Action::MoveUp) => view.move_selected(-1, &mut tui.state),
+            (RequestMode::Normal, Action::Collapse) => view.collapse(),
+            (RequestMode::Normal, Action::Expand) => view.expand(),
+            (RequestMode::Normal, Action::Toggle) => view.toggle(),
+            (RequestMode::Normal, Action::Edit) => view.edit(),
+            (RequestMode::Normal, Action::New) => view.new_request(),
+            (RequestMode::Normal, Action::Import) => view.import(),
+            (RequestMode::Normal, Action::Export) => view.export(),
+            (RequestMode::Normal, Action::Discover) => view.discover(&mut tui.state),
+            (RequestMode::Normal, Action::Send) => view.send(&mut tui.state),
+            (RequestMode::Normal, Action::Cancel) => view.cancel(),
+            (RequestMode::Normal, Action::SaveAs) => view.save_as(),
+            (RequestMode::Normal, Action::Download) => view.download(),
+            (RequestMode::Normal, Action::Load) => view.load(),
+            (RequestMode::Normal, Action::Share) => view.share(&mut tui.state),
+            (RequestMode::Normal, Action::Duplicate) => view.duplicate(&mut tui.state),
+            (RequestMode::Normal, Action::Delete) => view.delete(&mut tui.state),
+            (RequestMode::Normal, Action::Search) => view.search.start(),
+            (RequestMode::Normal, Action::ClearSearch) => view.search.clear(),
+            (RequestMode::Insert(_, field), Action::ToggleEnabled) if field.is_grid() => {
+                view.grid.toggle_enabled()
+            }
+            (RequestMode::Insert(_, field), Action::ToggleSecret) if field.is_grid() => {
+                view.grid.toggle_secret()
+            }
+            (RequestMode::Insert(_, _), Action::NextBodyMode) => {
+                view.next_body_mode(&mut tui.state)
+            }
+            (RequestMode::Insert(_, _), Action::NextField) => view.next_edit(&mut tui.state),
+            (RequestMode::Insert(_, _), Action::Close) => view.save(&mut tui.state),
+            (RequestMode::Import(_), Action::Close) => view.save_import(&mut tui.state),
+            (RequestMode::Export(_, _), Action::NextFormat) => view.next_format(),
+            (RequestMode::Export(_, _), Action::ToggleToken) => view.toggle_token(),
+            (RequestMode::Export(_, _), Action::Copy) => view.copy_export(),
+            (RequestMode::Export(_, _), Action::Write) => view.write_export(),
+            (RequestMode::Save(_, _), Action::Confirm) => view.confirm_save(&mut tui.state),
+            (RequestMode::Load(_), Action::Confirm) => tui.load_request(),
+            (RequestMode::Export(_, _), Action::Close) => view.close(),
+            (RequestMode::Save(_, _) | RequestMode::Load(_), Action::Close) => view.close(),
+            _ => {}
+        },
+        View::Runner(view) => match action {
+            Action::Down => view.down(),
+            Action::Up => view.up(),
+            Action::NextTag => view.next_tag(&mut tui.state),
+            Action::Junit => view.write_junit(),
+            _ => {}
+        },
+        View::Load(view) => {
+            if action == Action::Cancel {
+                view.cancel(&mut tui.state)
+            }
+        }
+    }
+    true
+}
+
+/// Runs a command typed after `:`, returns false to quit.
+fn execute(tui: &mut TUI, command: command::Command) -> bool {
+    use command::Command;
+
+    let status = match command {
+        Command::Action(action) => return perform(tui, action),
+        Command::Env(name) => k8s::use_context(&name)
+            .map(|context| format!("using {context}, refresh to list its apps")),
+        Command::Ns(ns) => {
+            k8s::use_namespace(&ns);
+            Ok(format!("listing apps in {ns}, refresh to list them"))
+        }
+        Command::ImportCurl(curl) => tui.import_curl(&curl).map(|_| String::new()),
+        Command::ExportPostman(file) => tui
+            .export_postman(file)
+            .map(|file| format!("wrote {file}")),
+        Command::Logs => tui.show_logs().map(|_| String::new()),
+        Command::Restart => tui
+            .restart()
+            .map(|pod| format!("deleted {pod}, its deployment starts a new one")),
+        Command::Theme(None) => Ok(format!("theme {}", theme::next())),
+        Command::Theme(Some(name)) => match theme::select(&name) {
+            true => Ok(format!("theme {name}")),
+            false => Err(eyre!("unknown theme {name}")),
+        },
+        Command::Filter(query) => tui.filter(&query).map(|_| String::new()),
+    };
+
+    tui.status = match status {
+        Ok(status) if status.is_empty() => None,
+        Ok(status) => Some(status),
+        Err(err) => Some(format!("{err:#}")),
+    };
+    true
+}
+
+/// Types in a text field, where only multiline fields take up/down and enter.
+fn edit_key(edit: &mut impl Editing, code: KeyCode, multiline: bool) {
+    match code {
+        KeyCode::Left => edit.move_cursor(Direction::Left, 1),
+        KeyCode::Right => edit.move_cursor(Direction::Right, 1),
+        KeyCode::Up if multiline => edit.move_cursor(Direction::Up, 1),
+        KeyCode::Down if multiline => edit.move_cursor(Direction::Down, 1),
+        KeyCode::Char(n) => edit.add_char(n),
+        KeyCode::Enter if multiline => edit.new_line(),
+        KeyCode::Backspace => edit.del_char(),
+        KeyCode::End => edit.move_cursor(Direction::Right, usize::MAX),
+        KeyCode::Home => edit.move_cursor(Direction::Left, usize::MAX),
+        _ => {}
+    }
+}
+
+/// Edits the search while it is typed, returns whether the query changed.
+fn search_key(search: &mut Search, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Esc => search.clear(),
+        KeyCode::Backspace => search.del_char(),
+        KeyCode::Char(c) => search.add_char(c),
+        KeyCode::Enter => {
+            search.confirm();
+            return false;
+        }
+        _ => return false,
+    }
+    true
+}
+
 pub fn install_hooks() -> color_eyre::Result<()> {
     let hook_builder = color_eyre::config::HookBuilder::default();
     let (panic_hook, eyre_hook) = hook_builder.into_hooks();
@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+
+use crate::state::{App, Sample, Settings, State, HISTORY};
+
+/// The apps in a state file by name, see `AppSnapshot`.
+pub type Snapshot = BTreeMap<String, AppSnapshot>;
+
+/// What a merge compares of an app: the modified time of each of its requests by id, the order
+/// of the requests, its variables and its settings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppSnapshot {
+    requests: BTreeMap<String, u64>,
+    order: Vec<String>,
+    vars: BTreeMap<String, String>,
+    settings: Settings,
+}
+
+impl From<&App> for AppSnapshot {
+    fn from(app: &App) -> Self {
+        Self {
+            requests: app
+                .requests
+                .iter()
+                .map(|r| (r.id.clone(), r.modified))
+                .collect(),
+            order: app.requests.iter().map(|r| r.id.clone()).collect(),
+            vars: app.vars.clone(),
+            settings: app.settings.clone(),
+        }
+    }
+}
+
+pub fn snapshot(state: &State) -> Snapshot {
+    state
+        .values()
+        .into_iter()
+        .map(|app| (app.name.clone(), AppSnapshot::from(app)))
+        .collect()
+}
+
+/// Merges the state another instance saved into ours, returns whether ours changed.
+/// `base` is the file as we last read or wrote it, which tells a change on one side from one
+/// on the other, see `merge_app`.
+pub fn merge(ours: &mut State, theirs: &State, base: &Snapshot) -> bool {
+    let mut changed = false;
+    for app in theirs.values() {
+        match ours.get_mut(&app.name) {
+            Some(ours) => changed |= merge_app(ours, app, base.get(&app.name)),
+            // removed here since we last saved
+            None if base.contains_key(&app.name) => {}
+            None => {
+                ours.insert(app.clone());
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Merges their copy of an app into ours, returns whether ours changed. The most recently
+/// modified copy of a request wins, and the histories are combined. The order of the requests,
+/// variables and settings they changed since `base` are taken, unless we changed them too. The
+/// rest is kept as we have it.
+pub fn merge_app(ours: &mut App, theirs: &App, base: Option<&AppSnapshot>) -> bool {
+    let we_reordered = base.is_some_and(|base| reordered(ours, &base.order));
+    let mut changed = merge_requests(ours, theirs, base.map(|base| &base.requests));
+    let Some(base) = base else {
+        return changed;
+    };
+
+    if reordered(theirs, &base.order) && !we_reordered {
+        changed |= reorder(ours, theirs);
+    }
+
+    let keys = theirs
+        .vars
+        .keys()
+        .chain(base.vars.keys())
+        .cloned()
+        .collect::<Vec<_>>();
+    for key in keys {
+        let (was, now) = (base.vars.get(&key), theirs.vars.get(&key));
+        if was == now || ours.vars.get(&key) != was {
+            continue;
+        }
+        match now {
+            Some(value) => ours.vars.insert(key, value.clone()),
+            None => ours.vars.remove(&key),
+        };
+        changed = true;
+    }
+
+    if theirs.settings != base.settings && ours.settings == base.settings {
+        ours.settings = theirs.settings.clone();
+        changed = true;
+    }
+    changed
+}
+
+fn merge_requests(ours: &mut App, theirs: &App, base: Option<&BTreeMap<String, u64>>) -> bool {
+    let known = |id: &str| base.and_then(|base| base.get(id)).copied();
+    let before = ours.requests.clone();
+
+    // deleted there, unless edited here since
+    ours.requests.retain(|r| {
+        theirs.requests.iter().any(|t| t.id == r.id) || known(&r.id) != Some(r.modified)
+    });
+
+    for request in &theirs.requests {
+        match ours.requests.iter_mut().find(|r| r.id == request.id) {
+            Some(ours) => {
+                let history = merge_history(&ours.history, &request.history);
+                if request.modified > ours.modified {
+                    *ours = request.clone();
+                }
+                ours.history = history;
+            }
+            // deleted here, unless edited there since
+            None if known(&request.id).is_some_and(|m| m >= request.modified) => {}
+            None => ours.requests.push(request.clone()),
+        }
+    }
+
+    ours.requests != before
+}
+
+/// Whether the requests the app has of `order` are in another order.
+fn reordered(app: &App, order: &[String]) -> bool {
+    let ids = app
+        .requests
+        .iter()
+        .map(|r| &r.id)
+        .filter(|id| order.contains(id));
+    let kept = order
+        .iter()
+        .filter(|id| app.requests.iter().any(|r| r.id == **id));
+    !ids.eq(kept)
+}
+
+/// Puts our requests in their order, a request they don't have stays after the one before it.
+/// Returns whether the order changed.
+fn reorder(ours: &mut App, theirs: &App) -> bool {
+    let before = ours
+        .requests
+        .iter()
+        .map(|r| r.id.clone())
+        .collect::<Vec<_>>();
+    let mut at = 0;
+    let mut keyed = ours
+        .requests
+        .drain(..)
+        .map(|r| {
+            if let Some(i) = theirs.requests.iter().position(|t| t.id == r.id) {
+                at = i;
+            }
+            (at, r)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by_key(|(at, _)| *at);
+    ours.requests = keyed.into_iter().map(|(_, r)| r).collect();
+
+    ours.requests.iter().map(|r| &r.id).ne(before.iter())
+}
+
+/// Both histories in the order they were sent, keeping the latest `HISTORY`.
+fn merge_history(ours: &[Sample], theirs: &[Sample]) -> Vec<Sample> {
+    let mut history = ours.to_vec();
+    for sample in theirs {
+        if !history.contains(sample) {
+            history.push(sample.clone());
+        }
+    }
+    history.sort_by_key(|s| s.at);
+
+    let excess = history.len().saturating_sub(HISTORY);
+    history.drain(..excess);
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::Request;
+
+    use super::*;
+
+    fn request(id: &str, path: &str, modified: u64) -> Request {
+        Request {
+            id: id.into(),
+            path: path.into(),
+            modified,
+            ..Default::default()
+        }
+    }
+
+    fn state(requests: Vec<Request>) -> State {
+        let mut app = App::named("utsjekk");
+        app.requests = requests;
+        let mut state = State::default();
+        state.insert(app);
+        state
+    }
+
+    fn paths(state: &State) -> Vec<&str> {
+        let app = state.get("utsjekk").unwrap();
+        app.requests.iter().map(|r| r.path.as_str()).collect()
+    }
+
+    #[test]
+    fn newest_edit_wins() {
+        let base = state(vec![request("a", "/a", 1), request("b", "/b", 1)]);
+        let mut ours = state(vec![request("a", "/ours", 3), request("b", "/b", 1)]);
+        let theirs = state(vec![request("a", "/theirs", 2), request("b", "/theirs", 2)]);
+
+        assert!(merge(&mut ours, &theirs, &snapshot(&base)));
+        assert_eq!(paths(&ours), vec!["/ours", "/theirs"]);
+        assert!(!merge(&mut ours.clone(), &ours, &snapshot(&ours)));
+    }
+
+    #[test]
+    fn deletions_and_additions_on_either_side() {
+        let base = state(vec![request("a", "/a", 1), request("b", "/b", 1)]);
+        // we deleted a and added c, they deleted b and added d
+        let mut ours = state(vec![request("b", "/b", 1), request("c", "/c", 2)]);
+        let theirs = state(vec![request("a", "/a", 1), request("d", "/d", 2)]);
+
+        merge(&mut ours, &theirs, &snapshot(&base));
+        assert_eq!(paths(&ours), vec!["/c", "/d"]);
+    }
+
+    #[test]
+    fn edits_survive_deletion_on_the_other_side() {
+        let base = state(vec![request("a", "/a", 1)]);
+        let mut ours = state(vec![request("a", "/edited", 2)]);
+
+        merge(&mut ours, &state(vec![]), &snapshot(&base));
+        assert_eq!(paths(&ours), vec!["/edited"]);
+    }
+
+    #[test]
+    fn edits_within_a_second_and_reorders() {
+        let base = state(vec![request("a", "/a", 1000), request("b", "/b", 1000)]);
+        let mut ours = state(vec![request("a", "/ours", 1200), request("b", "/b", 1000)]);
+        let mut theirs = state(vec![
+            request("a", "/theirs", 1700),
+            request("b", "/b", 1000),
+        ]);
+        theirs.get_mut("utsjekk").unwrap().move_request("b", -1);
+
+        assert!(merge(&mut ours, &theirs, &snapshot(&base)));
+        assert_eq!(paths(&ours), vec!["/b", "/theirs"]);
+    }
+
+    #[test]
+    fn vars_and_settings_changed_on_either_side() {
+        let mut base = state(vec![]);
+        let app = base.get_mut("utsjekk").unwrap();
+        app.vars = BTreeMap::from([("a".into(), "1".into()), ("b".into(), "1".into())]);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        let app = ours.get_mut("utsjekk").unwrap();
+        app.vars.insert("a".into(), "ours".into());
+        let app = theirs.get_mut("utsjekk").unwrap();
+        app.vars.insert("a".into(), "theirs".into());
+        app.vars.remove("b");
+        app.vars.insert("c".into(), "theirs".into());
+        app.settings.retries = Some(2);
+
+        assert!(merge(&mut ours, &theirs, &snapshot(&base)));
+        let app = ours.get("utsjekk").unwrap();
+        let vars = app
+            .vars
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(vars, vec![("a", "ours"), ("c", "theirs")]);
+        assert_eq!(app.settings.retries, Some(2));
+    }
+
+    #[test]
+    fn histories_are_combined() {
+        let sample = |at| Sample {
+            at,
+            status: 200,
+            ms: 10,
+            load: None,
+        };
+        let mut a = request("a", "/a", 1);
+        a.history = vec![sample(1), sample(3)];
+        let mut ours = state(vec![a.clone()]);
+        a.history = vec![sample(1), sample(2)];
+        let theirs = state(vec![a]);
+
+        merge(&mut ours, &theirs, &snapshot(&theirs));
+        let history = &ours.get("utsjekk").unwrap().requests[0].history;
+        assert_eq!(history, &vec![sample(1), sample(2), sample(3)]);
+    }
+}
@@ -0,0 +1,166 @@
+use color_eyre::eyre::{bail, eyre, Result};
+use serde_json::Value;
+
+use crate::state::uuid;
+
+/// The version of the state written by this build. Bump it when a change to `State`, `App` or
+/// `Request` can't be read from older files with serde defaults alone, and add a migration.
+pub const VERSION: u64 = 3;
+
+/// Upgrades the document from `MIGRATIONS[n]`, for each version n below `VERSION`.
+const MIGRATIONS: [fn(&mut Value) -> Result<()>; VERSION as usize] =
+    [v0_scrub_secrets, v1_request_ids, v2_modified_millis];
+
+/// The version of a state document, files from before versioning are version 0.
+pub fn version(state: &Value) -> u64 {
+    state.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades the state document to the current version step by step.
+/// Fields the migrations don't know about are left as they are.
+pub fn migrate(state: &mut Value) -> Result<()> {
+    let from = version(state);
+    if from > VERSION {
+        bail!("the state is version {from}, this build only reads up to version {VERSION}");
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(state).map_err(|err| eyre!("migration from version {version} failed: {err}"))?;
+        state["version"] = Value::from(version as u64 + 1);
+    }
+    Ok(())
+}
+
+/// Version 0 saved the Azure client secrets in plaintext, they are now only kept in memory.
+fn v0_scrub_secrets(state: &mut Value) -> Result<()> {
+    if !state.is_object() {
+        bail!("expected an object");
+    }
+    remove_secrets(state);
+    Ok(())
+}
+
+/// Version 1 identified requests by a number, a hash of their content or their position.
+/// They now have a UUID, derived from the number so the shared copies of them still match.
+fn v1_request_ids(state: &mut Value) -> Result<()> {
+    let Some(apps) = state.get_mut("apps").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+
+    for app in apps.values_mut() {
+        let Some(requests) = app.get_mut("requests").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for request in requests {
+            if let Some(n) = request.get("id").and_then(Value::as_u64) {
+                request["id"] = Value::from(legacy_id(n));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Version 2 had the modified time of requests in seconds, too coarse to order edits made by two
+/// instances within the same second. It is now in milliseconds.
+fn v2_modified_millis(state: &mut Value) -> Result<()> {
+    let Some(apps) = state.get_mut("apps").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+
+    for app in apps.values_mut() {
+        let Some(requests) = app.get_mut("requests").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for request in requests {
+            if let Some(secs) = request.get("modified").and_then(Value::as_u64) {
+                request["modified"] = Value::from(secs.saturating_mul(1000));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The UUID of a request numbered before version 2, the number in its last eight bytes.
+pub fn legacy_id(n: u64) -> String {
+    let mut bytes = [0; 16];
+    bytes[8..].copy_from_slice(&n.to_be_bytes());
+    uuid(bytes)
+}
+
+/// Removes the `client_secret` of every app, returns whether there were any.
+pub fn remove_secrets(state: &mut Value) -> bool {
+    let Some(apps) = state.get_mut("apps").and_then(Value::as_object_mut) else {
+        return false;
+    };
+
+    let mut removed = false;
+    for app in apps.values_mut() {
+        if let Some(azure) = app.get_mut("azure").and_then(Value::as_object_mut) {
+            removed |= azure.remove("client_secret").is_some();
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn migrates_unversioned_state() {
+        let mut state = json!({
+            "apps": {
+                "utsjekk": {
+                    "azure": { "client_id": "id", "client_secret": "hemmelig" },
+                    "unknown": true
+                }
+            }
+        });
+
+        migrate(&mut state).unwrap();
+
+        assert_eq!(version(&state), VERSION);
+        assert_eq!(state["apps"]["utsjekk"]["azure"], json!({ "client_id": "id" }));
+        assert_eq!(state["apps"]["utsjekk"]["unknown"], json!(true));
+    }
+
+    #[test]
+    fn numbered_requests_get_uuids() {
+        let mut state = json!({
+            "version": 1,
+            "apps": { "utsjekk": { "requests": [{ "id": 1 }, { "id": 14_000_000_000_000_000_000u64 }] } }
+        });
+
+        migrate(&mut state).unwrap();
+
+        let requests = &state["apps"]["utsjekk"]["requests"];
+        assert_eq!(requests[0]["id"], "00000000-0000-0000-0000-000000000001");
+        assert_eq!(requests[1]["id"], "00000000-0000-0000-c249-fdd327780000");
+    }
+
+    #[test]
+    fn modified_times_in_millis() {
+        let mut state = json!({
+            "version": 2,
+            "apps": { "utsjekk": { "requests": [{ "id": "a", "modified": 1_700_000_000 }, { "id": "b" }] } }
+        });
+
+        migrate(&mut state).unwrap();
+
+        let requests = &state["apps"]["utsjekk"]["requests"];
+        assert_eq!(requests[0]["modified"], 1_700_000_000_000u64);
+        assert_eq!(requests[1].get("modified"), None);
+    }
+
+    #[test]
+    fn refuses_newer_and_broken_state() {
+        let mut newer = json!({ "version": VERSION + 1, "apps": {} });
+        assert!(migrate(&mut newer).is_err());
+
+        let mut broken = json!([]);
+        let err = migrate(&mut broken).unwrap_err();
+        assert!(err.to_string().contains("from version 0"));
+    }
+}
@@ -0,0 +1,767 @@
+use std::fmt::Display;
+
+use color_eyre::eyre::{bail, eyre, Result};
+use futures::executor::block_on;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    client::{Client, Response},
+    state::{App, BodyMode, Header, Method, Operation, Param, Request, SyncStatus},
+};
+
+/// Where springdoc publishes the OpenAPI document.
+pub const DOCS: &str = "/v3/api-docs";
+
+const METHODS: [(&str, Method); 5] = [
+    ("get", Method::Get),
+    ("post", Method::Post),
+    ("put", Method::Put),
+    ("patch", Method::Patch),
+    ("delete", Method::Delete),
+];
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} changed, {} removed",
+            self.added, self.changed, self.removed
+        )
+    }
+}
+
+/// Fetches the OpenAPI document from the host, e.g. a port-forward, or the host of the app (see
+/// `App::host`), and syncs the app's requests with its operations.
+pub fn discover(app: &mut App, host: Option<&str>) -> Result<Summary> {
+    let host = match host {
+        Some(host) => host.to_string(),
+        None => app
+            .host()
+            .map(String::from)
+            .ok_or_else(|| eyre!("{} has no hosts", app.name))?,
+    };
+
+    let token = app.token()?;
+    let req = Request::new(
+        Method::Get,
+        DOCS,
+        vec![Header::new("Accept".into(), "application/json".into())],
+        "",
+    );
+
+    let res = block_on(Client::new(&host, token, app.settings.clone()).send(&req))?;
+    if res.status != 200 {
+        bail!("{host}{DOCS} returned {}", res.status);
+    }
+
+    let doc = serde_json::from_str::<Value>(&res.body)?;
+    store(app, doc)
+}
+
+/// Syncs the app's requests with the document and keeps it for validating bodies.
+pub fn store(app: &mut App, doc: Value) -> Result<Summary> {
+    let summary = sync(app, generate(&doc)?);
+    app.openapi = Some(doc);
+    Ok(summary)
+}
+
+/// Generates a request per operation, with path params as `{{var}}` placeholders,
+/// required query params and headers, and an example body built from the schema.
+pub fn generate(doc: &Value) -> Result<Vec<Request>> {
+    let paths = doc
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| eyre!("no paths in OpenAPI document"))?;
+
+    let param = Regex::new(r"\{([^}]+)\}")?;
+    let mut requests = vec![];
+
+    for (path, item) in paths {
+        for (key, method) in METHODS {
+            let Some(op) = item.get(key) else {
+                continue;
+            };
+
+            let params = [item.get("parameters"), op.get("parameters")]
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_array)
+                .flatten()
+                .map(|p| resolve(p, doc))
+                .filter(|p| p.get("required").and_then(Value::as_bool) == Some(true))
+                .collect::<Vec<_>>();
+
+            let named = |location: &str| {
+                params
+                    .iter()
+                    .filter(|p| p.get("in").and_then(Value::as_str) == Some(location))
+                    .filter_map(|p| p.get("name").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+            };
+
+            let template = param.replace_all(path, "{{$1}}").to_string();
+            let query = named("query")
+                .iter()
+                .map(|name| Param::new(name, &format!("{{{{{name}}}}}")))
+                .collect::<Vec<_>>();
+
+            let mut headers = named("header")
+                .iter()
+                .map(|name| Header::new(name.to_string(), format!("{{{{{name}}}}}")))
+                .collect::<Vec<_>>();
+
+            let schema = op
+                .get("requestBody")
+                .map(|body| resolve(body, doc))
+                .and_then(|body| body.get("content").and_then(Value::as_object).cloned())
+                .and_then(|content| {
+                    content
+                        .iter()
+                        .find(|(kind, _)| kind.contains("json"))
+                        .and_then(|(_, media)| media.get("schema").cloned())
+                });
+
+            let body = match schema {
+                Some(schema) => {
+                    headers.push(Header::new(
+                        "Content-Type".into(),
+                        "application/json".into(),
+                    ));
+                    serde_json::to_string_pretty(&example(&schema, doc, 0))?
+                }
+                None => String::new(),
+            };
+
+            // only what is generated from the spec, so new fields of requests don't change it
+            let canonical = json!({
+                "method": method.to_string(),
+                "path": template,
+                "query": query.iter().map(|p| [&p.key, &p.value]).collect::<Vec<_>>(),
+                "headers": headers.iter().map(|h| [&h.key, &h.value]).collect::<Vec<_>>(),
+                "body": body,
+            });
+            let hash = fnv(canonical.to_string().as_bytes());
+
+            let mut request = Request::new(method.clone(), &template, headers, &body);
+            request.query = query;
+            request.desc = ["summary", "operationId"]
+                .iter()
+                .find_map(|key| op.get(key).and_then(Value::as_str))
+                .unwrap_or_default()
+                .to_string();
+            request.folder = op
+                .pointer("/tags/0")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .replace('/', "-");
+            request.operation = Some(Operation {
+                id: format!("{method} {path}"),
+                hash,
+                status: SyncStatus::Synced,
+            });
+
+            requests.push(request);
+        }
+    }
+
+    Ok(requests)
+}
+
+/// Adds new operations, and marks requests whose operation changed or is no longer in the spec.
+/// Edits to generated requests are kept, the marks are only cleared by editing the request.
+pub fn sync(app: &mut App, generated: Vec<Request>) -> Summary {
+    let mut summary = Summary::default();
+
+    for req in &mut app.requests {
+        let Some(op) = &mut req.operation else {
+            continue;
+        };
+
+        match generated
+            .iter()
+            .filter_map(|g| g.operation.as_ref())
+            .find(|g| g.id == op.id)
+        {
+            None if op.status == SyncStatus::Removed => {}
+            None => {
+                op.status = SyncStatus::Removed;
+                summary.removed += 1;
+            }
+            // saved with the hash of an older version, which can't be compared
+            Some(new) if op.hash == 0 => op.hash = new.hash,
+            Some(new) if new.hash != op.hash => {
+                op.hash = new.hash;
+                op.status = SyncStatus::Changed;
+                summary.changed += 1;
+            }
+            Some(_) if op.status == SyncStatus::Removed => op.status = SyncStatus::Synced,
+            Some(_) => {}
+        }
+    }
+
+    for req in generated {
+        let exists = app
+            .requests
+            .iter()
+            .any(|r| r.operation.as_ref().map(|o| &o.id) == req.operation.as_ref().map(|o| &o.id));
+
+        if !exists {
+            summary.added += 1;
+            app.add_request(req);
+        }
+    }
+
+    summary
+}
+
+/// The 64-bit FNV-1a hash, which unlike the std hashers is the same across builds.
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Follows local `$ref`s like `#/components/schemas/Sak`.
+fn resolve<'a>(value: &'a Value, doc: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..16 {
+        match value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| doc.pointer(pointer))
+        {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+fn example(schema: &Value, doc: &Value, depth: usize) -> Value {
+    if depth > 8 {
+        return Value::Null;
+    }
+
+    let schema = resolve(schema, doc);
+
+    for key in ["example", "default"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+
+    if let Some(value) = schema.pointer("/enum/0") {
+        return value.clone();
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in all {
+            if let Value::Object(fields) = example(part, doc, depth + 1) {
+                merged.extend(fields);
+            }
+        }
+        return Value::Object(merged);
+    }
+
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.pointer(&format!("/{key}/0")))
+    {
+        return example(first, doc, depth + 1);
+    }
+
+    // OpenAPI 3.1 allows a list of types, e.g. ["string", "null"]
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "null",
+    };
+
+    match kind {
+        "object" => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(name, prop)| (name.clone(), example(prop, doc, depth + 1)))
+                .collect(),
+        ),
+        "array" => match schema.get("items") {
+            Some(items) => Value::Array(vec![example(items, doc, depth + 1)]),
+            None => Value::Array(vec![]),
+        },
+        "string" => match schema.get("format").and_then(Value::as_str) {
+            Some("date") => "2024-01-01".into(),
+            Some("date-time") => "2024-01-01T12:00:00Z".into(),
+            Some("uuid") => "00000000-0000-0000-0000-000000000000".into(),
+            _ => "string".into(),
+        },
+        "integer" => 0.into(),
+        "number" => 0.0.into(),
+        "boolean" => false.into(),
+        _ => Value::Null,
+    }
+}
+
+/// A value that does not match the schema, located by its JSON pointer in the body.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+/// Validates the request body against the requestBody schema of its operation.
+/// Requests without a matching operation or schema have nothing to violate, and only JSON bodies are checked.
+pub fn validate_request(doc: &Value, req: &Request) -> Vec<Violation> {
+    let Some(body) = operation(doc, req).and_then(|op| op.get("requestBody")) else {
+        return vec![];
+    };
+
+    let body = resolve(body, doc);
+    if !req.has_body() {
+        return match body.get("required").and_then(Value::as_bool) {
+            Some(true) => vec![violation("", "request body is required")],
+            _ => vec![],
+        };
+    }
+
+    match json_schema(body) {
+        Some(schema) if req.body_mode == BodyMode::Json => validate_body(schema, &req.body, doc),
+        _ => vec![],
+    }
+}
+
+/// Validates the response body against the schema declared for its status,
+/// falling back to the status range (e.g. `2XX`) and `default`.
+pub fn validate_response(doc: &Value, req: &Request, res: &Response) -> Vec<Violation> {
+    let Some(responses) = operation(doc, req).and_then(|op| op.get("responses")) else {
+        return vec![];
+    };
+
+    let status = res.status.to_string();
+    let range = format!("{}XX", &status[..1]);
+    let declared = [status.as_str(), range.as_str(), "default"]
+        .iter()
+        .find_map(|key| responses.get(key));
+
+    match declared {
+        None => vec![violation("", &format!("status {status} is not declared"))],
+        Some(declared) => match json_schema(resolve(declared, doc)) {
+            Some(schema) => validate_body(schema, &res.body, doc),
+            None => vec![],
+        },
+    }
+}
+
+/// Finds the operation by the id it was generated from, or by matching the path templates.
+fn operation<'a>(doc: &'a Value, req: &Request) -> Option<&'a Value> {
+    let method = req.method.to_string().to_lowercase();
+    let paths = doc.get("paths")?.as_object()?;
+
+    if let Some((_, path)) = req.operation.as_ref().and_then(|op| op.id.split_once(' ')) {
+        if let Some(op) = paths.get(path).and_then(|item| item.get(&method)) {
+            return Some(op);
+        }
+    }
+
+    let path = req.path.split('?').next().unwrap_or_default();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    paths
+        .iter()
+        .filter(|(template, _)| {
+            let templates = template.trim_matches('/').split('/').collect::<Vec<_>>();
+            templates.len() == segments.len()
+                && templates.iter().zip(&segments).all(|(t, s)| {
+                    (t.starts_with('{') && t.ends_with('}') && !s.is_empty()) || t == s
+                })
+        })
+        .find_map(|(_, item)| item.get(&method))
+}
+
+fn json_schema(body: &Value) -> Option<&Value> {
+    body.get("content")?
+        .as_object()?
+        .iter()
+        .find(|(kind, _)| kind.contains("json"))
+        .and_then(|(_, media)| media.get("schema"))
+}
+
+fn validate_body(schema: &Value, body: &str, doc: &Value) -> Vec<Violation> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) => {
+            let mut violations = vec![];
+            validate(schema, &value, doc, "", &mut violations);
+            violations
+        }
+        Err(err) => vec![violation("", &format!("body is not valid JSON: {err}"))],
+    }
+}
+
+fn validate(schema: &Value, value: &Value, doc: &Value, pointer: &str, out: &mut Vec<Violation>) {
+    let schema = resolve(schema, doc);
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        all.iter()
+            .for_each(|part| validate(part, value, doc, pointer, out));
+    }
+
+    for key in ["oneOf", "anyOf"] {
+        if let Some(options) = schema.get(key).and_then(Value::as_array) {
+            let matches = options.iter().any(|option| {
+                let mut violations = vec![];
+                validate(option, value, doc, pointer, &mut violations);
+                violations.is_empty()
+            });
+            if !matches {
+                out.push(violation(
+                    pointer,
+                    &format!("does not match any schema in {key}"),
+                ));
+            }
+        }
+    }
+
+    let kinds = match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ if schema.get("properties").is_some() => vec!["object"],
+        _ => vec![],
+    };
+
+    if value.is_null()
+        && (kinds.contains(&"null") || schema.get("nullable") == Some(&Value::Bool(true)))
+    {
+        return;
+    }
+
+    if !kinds.is_empty() && !kinds.iter().any(|kind| is_type(kind, value)) {
+        let message = format!("expected {}, got {}", kinds.join(" or "), type_of(value));
+        out.push(violation(pointer, &message));
+        return;
+    }
+
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        if !values.contains(value) {
+            let expected = values.iter().map(Value::to_string).collect::<Vec<_>>();
+            let message = format!("expected one of {}, got {value}", expected.join(", "));
+            out.push(violation(pointer, &message));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .filter(|name| !fields.contains_key(*name))
+            {
+                out.push(violation(
+                    &child(pointer, name),
+                    "required property is missing",
+                ));
+            }
+
+            for (name, field) in fields {
+                match properties.and_then(|p| p.get(name)) {
+                    Some(property) => validate(property, field, doc, &child(pointer, name), out),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        out.push(violation(&child(pointer, name), "unexpected property"))
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item) = schema.get("items") {
+                for (i, value) in items.iter().enumerate() {
+                    validate(item, value, doc, &child(pointer, &i.to_string()), out);
+                }
+            }
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    out.push(violation(
+                        pointer,
+                        &format!("expected at least {min} items"),
+                    ));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if (items.len() as u64) > max {
+                    out.push(violation(pointer, &format!("expected at most {max} items")));
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    out.push(violation(
+                        pointer,
+                        &format!("expected at least {min} characters"),
+                    ));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    out.push(violation(
+                        pointer,
+                        &format!("expected at most {max} characters"),
+                    ));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                if Regex::new(pattern).is_ok_and(|re| !re.is_match(text)) {
+                    out.push(violation(pointer, &format!("does not match {pattern}")));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    out.push(violation(pointer, &format!("expected at least {min}")));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    out.push(violation(pointer, &format!("expected at most {max}")));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_type(kind: &str, value: &Value) -> bool {
+    match kind {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_of(value: &Value) -> &str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Appends a reference token to a JSON pointer, escaping `~` and `/` as RFC 6901 requires.
+fn child(pointer: &str, token: &str) -> String {
+    format!("{pointer}/{}", token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn violation(pointer: &str, message: &str) -> Violation {
+    Violation {
+        pointer: pointer.into(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn spec(summary: &str) -> Value {
+        json!({
+            "openapi": "3.0.1",
+            "paths": {
+                "/api/sak/{sakId}": {
+                    "parameters": [{ "name": "sakId", "in": "path", "required": true }],
+                    "put": {
+                        "tags": ["sak"],
+                        "summary": summary,
+                        "parameters": [
+                            { "name": "dry", "in": "query", "required": true },
+                            { "name": "Nav-Call-Id", "in": "header", "required": true },
+                            { "name": "debug", "in": "query" }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Sak" } }
+                            }
+                        }
+                    }
+                },
+                "/actuator/live": { "get": { "operationId": "live" } }
+            },
+            "components": {
+                "schemas": {
+                    "Sak": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string", "format": "uuid" },
+                            "belop": { "type": "integer" },
+                            "status": { "type": "string", "enum": ["OPPRETTET", "AVSLUTTET"] },
+                            "linjer": { "type": "array", "items": { "type": "string", "example": "linje" } }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn generate_requests() {
+        let requests = generate(&spec("Oppdater sak")).unwrap();
+        let put = requests.iter().find(|r| r.method == Method::Put).unwrap();
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(put.path, "/api/sak/{{sakId}}");
+        assert_eq!(put.query, vec![Param::new("dry", "{{dry}}")]);
+        assert_eq!(put.desc, "Oppdater sak");
+        assert_eq!(put.folder, "sak");
+        assert!(put
+            .headers
+            .contains(&Header::new("Nav-Call-Id".into(), "{{Nav-Call-Id}}".into())));
+        assert_eq!(
+            serde_json::from_str::<Value>(&put.body).unwrap(),
+            json!({
+                "id": "00000000-0000-0000-0000-000000000000",
+                "belop": 0,
+                "status": "OPPRETTET",
+                "linjer": ["linje"]
+            })
+        );
+    }
+
+    #[test]
+    fn sync_marks_changed_and_removed() {
+        let mut app = App::named("utsjekk");
+
+        let first = sync(&mut app, generate(&spec("Oppdater sak")).unwrap());
+        assert_eq!(first.added, 2);
+
+        let mut changed = spec("Oppdater sak");
+        changed["paths"]["/api/sak/{sakId}"]["put"]["parameters"][0]["name"] = "force".into();
+        changed["paths"]
+            .as_object_mut()
+            .unwrap()
+            .remove("/actuator/live");
+
+        let second = sync(&mut app, generate(&changed).unwrap());
+        assert_eq!(
+            second,
+            Summary {
+                added: 0,
+                changed: 1,
+                removed: 1
+            }
+        );
+
+        let status = |id: &str| {
+            app.requests
+                .iter()
+                .find_map(|r| r.operation.as_ref().filter(|o| o.id == id))
+                .map(|o| o.status)
+        };
+        assert_eq!(status("PUT /api/sak/{sakId}"), Some(SyncStatus::Changed));
+        assert_eq!(status("GET /actuator/live"), Some(SyncStatus::Removed));
+    }
+
+    #[test]
+    fn hashes_are_stable_and_old_ones_adopted() {
+        assert_eq!(fnv(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv(b"a"), 0xaf63dc4c8601ec8c);
+
+        let mut app = App::named("utsjekk");
+        sync(&mut app, generate(&spec("Oppdater sak")).unwrap());
+        let hashes = |app: &App| {
+            app.requests
+                .iter()
+                .map(|r| r.operation.as_ref().unwrap().hash)
+                .collect::<Vec<_>>()
+        };
+        let generated = hashes(&app);
+        let json = serde_json::to_string(&app.requests[0]).unwrap();
+        let old = json.replace("\"spec_hash\"", "\"hash\"");
+        app.requests[0] = serde_json::from_str(&old).unwrap();
+
+        let summary = sync(&mut app, generate(&spec("Oppdater sak")).unwrap());
+        assert_eq!(summary, Summary::default());
+        assert_eq!(hashes(&app), generated);
+    }
+
+    #[test]
+    fn validate_bodies() {
+        let mut doc = spec("Oppdater sak");
+        doc["components"]["schemas"]["Sak"]["required"] = json!(["id", "belop"]);
+        doc["paths"]["/api/sak/{sakId}"]["put"]["responses"] = json!({
+            "2XX": { "content": { "application/json": { "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Sak" } } } } }
+        });
+
+        let req = Request::new(
+            Method::Put,
+            "/api/sak/123?dry=true",
+            vec![],
+            r#"{ "id": "1", "status": "ukjent", "linjer": ["a", 2] }"#,
+        );
+        let violations = validate_request(&doc, &req)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            violations,
+            vec![
+                "#/belop: required property is missing",
+                "#/linjer/1: expected string, got number",
+                "#/status: expected one of \"OPPRETTET\", \"AVSLUTTET\", got \"ukjent\"",
+            ]
+        );
+
+        let res = Response {
+            status: 201,
+            headers: vec![],
+            body: r#"[{ "id": "1", "belop": "100" }]"#.into(),
+            bytes: vec![],
+            elapsed: std::time::Duration::ZERO,
+            timing: Default::default(),
+            sizes: Default::default(),
+        };
+        assert_eq!(
+            validate_response(&doc, &req, &res),
+            vec![violation("/0/belop", "expected integer, got string")]
+        );
+    }
+}
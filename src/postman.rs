@@ -0,0 +1,563 @@
+use std::collections::BTreeMap;
+
+use color_eyre::eyre::Result;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    export,
+    state::{App, BodyMode, Header, Method, Node, Param, Request},
+};
+
+const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+const BASE_URL: &str = "baseUrl";
+
+/// The subset of the Postman v2.1 collection format that maps onto saved requests.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Collection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variable: Vec<Variable>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Info {
+    name: String,
+    schema: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Item {
+    name: String,
+    /// Postman's item id, the request id when exported from here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<Vec<Item>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request: Option<PostmanRequest>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PostmanRequest {
+    method: String,
+    #[serde(default)]
+    header: Vec<PostmanHeader>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Body>,
+    url: PostmanUrl,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<Description>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PostmanHeader {
+    key: String,
+    value: String,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Body {
+    mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    urlencoded: Vec<PostmanHeader>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    formdata: Vec<FormData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<File>,
+    /// The language of a raw body, as in `{ "raw": { "language": "json" } }`.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    options: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct FormData {
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    /// A path, or a list of paths in collections written by the Postman app.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    src: serde_json::Value,
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct File {
+    #[serde(default)]
+    src: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum PostmanUrl {
+    Raw(String),
+    Parts {
+        raw: Option<String>,
+        #[serde(default)]
+        path: Vec<String>,
+        #[serde(default)]
+        query: Vec<PostmanHeader>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum Description {
+    Text(String),
+    Content { content: String },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Variable {
+    key: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+/// Requests and variables read from a collection, and the items that could not be mapped.
+pub struct Import {
+    pub requests: Vec<Request>,
+    pub vars: BTreeMap<String, String>,
+    pub skipped: Vec<String>,
+}
+
+/// Reads a Postman v2.1 collection. Folders become request folders and
+/// collection variables become app variables, since both use `{{name}}`.
+pub fn import(json: &str) -> Result<Import> {
+    let collection = serde_json::from_str::<Collection>(json)?;
+    let mut import = Import {
+        requests: vec![],
+        vars: BTreeMap::new(),
+        skipped: vec![],
+    };
+
+    for var in collection.variable {
+        let value = match var.value {
+            serde_json::Value::String(value) => value,
+            value => value.to_string(),
+        };
+        import.vars.insert(var.key, value);
+    }
+
+    import_items(collection.item, &mut vec![], &mut import);
+    Ok(import)
+}
+
+/// Writes the requests of the app as a Postman v2.1 collection, with the first host as `{{baseUrl}}`.
+pub fn export(app: &App) -> Result<String> {
+    let mut variable = app
+        .hosts
+        .first()
+        .map(|host| match host.starts_with("http") {
+            true => host.clone(),
+            false => format!("https://{host}"),
+        })
+        .map(|host| Variable {
+            key: BASE_URL.into(),
+            value: host.into(),
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    variable.extend(app.vars.iter().map(|(key, value)| Variable {
+        key: key.clone(),
+        value: value.clone().into(),
+    }));
+
+    let collection = Collection {
+        info: Info {
+            name: app.name.clone(),
+            schema: SCHEMA.into(),
+        },
+        item: export_items(app.tree()),
+        variable,
+    };
+
+    Ok(serde_json::to_string_pretty(&collection)?)
+}
+
+fn import_items(items: Vec<Item>, folders: &mut Vec<String>, import: &mut Import) {
+    for item in items {
+        if let Some(children) = item.item {
+            folders.push(item.name.replace('/', "-"));
+            import_items(children, folders, import);
+            folders.pop();
+            continue;
+        }
+
+        let Some(req) = item.request else {
+            continue;
+        };
+
+        let method = match req.method.to_uppercase().as_str() {
+            "GET" => Method::Get,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "PATCH" => Method::Patch,
+            "DELETE" => Method::Delete,
+            other => {
+                import
+                    .skipped
+                    .push(format!("{} (unsupported method {other})", item.name));
+                continue;
+            }
+        };
+
+        let headers = req
+            .header
+            .into_iter()
+            .map(|h| Header {
+                enabled: !h.disabled,
+                ..Header::new(h.key, h.value)
+            })
+            .collect::<Vec<_>>();
+
+        let (body, body_mode, form) = match req.body {
+            None => (String::new(), BodyMode::Json, vec![]),
+            Some(body) => match body.mode.as_str() {
+                "raw" => {
+                    let language = body.options.pointer("/raw/language");
+                    let mode = match language.and_then(|l| l.as_str()) {
+                        None | Some("json") => BodyMode::Json,
+                        Some(_) => BodyMode::Raw,
+                    };
+                    (body.raw.unwrap_or_default(), mode, vec![])
+                }
+                "urlencoded" => {
+                    let form = body
+                        .urlencoded
+                        .into_iter()
+                        .map(|p| Param {
+                            enabled: !p.disabled,
+                            ..Param::new(&p.key, &p.value)
+                        })
+                        .collect();
+                    (String::new(), BodyMode::Form, form)
+                }
+                "formdata" => {
+                    let form = body
+                        .formdata
+                        .into_iter()
+                        .map(|p| {
+                            let value = match p.kind.as_str() {
+                                "file" => format!("@{}", src(&p.src)),
+                                _ => p.value.unwrap_or_default(),
+                            };
+                            Param {
+                                enabled: !p.disabled,
+                                ..Param::new(&p.key, &value)
+                            }
+                        })
+                        .collect();
+                    (String::new(), BodyMode::Multipart, form)
+                }
+                "file" => {
+                    let path = body.file.and_then(|f| f.src).unwrap_or_default();
+                    (path, BodyMode::File, vec![])
+                }
+                mode => {
+                    import
+                        .skipped
+                        .push(format!("{} (unsupported body {mode})", item.name));
+                    continue;
+                }
+            },
+        };
+
+        let (path, query) = path(req.url);
+        let mut request = Request::new(method, &path, headers, &body);
+        request.body_mode = body_mode;
+        request.form = form;
+        request.query = query;
+        if let Some(id) = item.id {
+            request.id = id;
+        }
+        request.folder = folders.join("/");
+        request.desc = match req.description {
+            Some(Description::Text(text) | Description::Content { content: text })
+                if !text.is_empty() =>
+            {
+                text
+            }
+            _ => item.name,
+        };
+
+        import.requests.push(request);
+    }
+}
+
+fn export_items(nodes: Vec<Node>) -> Vec<Item> {
+    nodes
+        .into_iter()
+        .map(|node| match node {
+            Node::Folder(name, children) => Item {
+                name,
+                id: None,
+                item: Some(export_items(children)),
+                request: None,
+            },
+            Node::Request(req) => Item {
+                name: match req.desc.is_empty() {
+                    true => format!("{} {}", req.method, req.path),
+                    false => req.desc.clone(),
+                },
+                id: Some(req.id.clone()),
+                item: None,
+                request: Some(PostmanRequest {
+                    method: req.method.to_string(),
+                    header: req
+                        .headers
+                        .iter()
+                        .map(|h| PostmanHeader {
+                            key: h.key.clone(),
+                            value: match h.is_secret() {
+                                true => format!("{{{{{}}}}}", export::placeholder(&h.key)),
+                                false => h.value.clone(),
+                            },
+                            disabled: !h.enabled,
+                        })
+                        .collect(),
+                    body: body(&req),
+                    url: PostmanUrl::Parts {
+                        raw: Some(format!("{{{{{BASE_URL}}}}}{}", req.path_with_query())),
+                        path: req
+                            .path
+                            .split('/')
+                            .filter(|s| !s.is_empty())
+                            .map(String::from)
+                            .collect(),
+                        query: req
+                            .query
+                            .iter()
+                            .map(|p| PostmanHeader {
+                                key: p.key.clone(),
+                                value: p.value.clone(),
+                                disabled: !p.enabled,
+                            })
+                            .collect(),
+                    },
+                    description: match req.desc.is_empty() {
+                        true => None,
+                        false => Some(Description::Text(req.desc.clone())),
+                    },
+                }),
+            },
+        })
+        .collect()
+}
+
+fn body(req: &Request) -> Option<Body> {
+    let params = || {
+        req.form.iter().map(|p| PostmanHeader {
+            key: p.key.clone(),
+            value: p.value.clone(),
+            disabled: !p.enabled,
+        })
+    };
+
+    match req.body_mode {
+        _ if req.body.is_empty() && req.form.is_empty() => None,
+        BodyMode::Json | BodyMode::Raw => {
+            let language = match req.body_mode {
+                BodyMode::Json => "json",
+                _ => "text",
+            };
+            Some(Body {
+                mode: "raw".into(),
+                raw: Some(req.body.clone()),
+                options: serde_json::json!({ "raw": { "language": language } }),
+                ..Body::default()
+            })
+        }
+        BodyMode::Form => Some(Body {
+            mode: "urlencoded".into(),
+            urlencoded: params().collect(),
+            ..Body::default()
+        }),
+        BodyMode::Multipart => Some(Body {
+            mode: "formdata".into(),
+            formdata: params()
+                .map(|p| match p.value.strip_prefix('@') {
+                    Some(path) => FormData {
+                        key: p.key,
+                        value: None,
+                        src: path.into(),
+                        kind: "file".into(),
+                        disabled: p.disabled,
+                    },
+                    None => FormData {
+                        key: p.key,
+                        value: Some(p.value),
+                        src: serde_json::Value::Null,
+                        kind: "text".into(),
+                        disabled: p.disabled,
+                    },
+                })
+                .collect(),
+            ..Body::default()
+        }),
+        BodyMode::File => Some(Body {
+            mode: "file".into(),
+            file: Some(File {
+                src: Some(req.body.clone()),
+            }),
+            ..Body::default()
+        }),
+    }
+}
+
+/// The first path of a form data file, which Postman writes as a string or a list.
+fn src(src: &serde_json::Value) -> String {
+    match src {
+        serde_json::Value::String(path) => path.clone(),
+        serde_json::Value::Array(paths) => paths
+            .first()
+            .and_then(|p| p.as_str())
+            .unwrap_or_default()
+            .into(),
+        _ => String::new(),
+    }
+}
+
+/// The path and query params of a Postman url, without the scheme, host or a leading `{{host}}` variable.
+/// The structured query is preferred over the raw url, as only it has the disabled params.
+fn path(url: PostmanUrl) -> (String, Vec<Param>) {
+    let (raw, parts) = match url {
+        PostmanUrl::Raw(raw) => (raw, vec![]),
+        PostmanUrl::Parts {
+            raw: Some(raw),
+            query,
+            ..
+        } => (raw, query),
+        PostmanUrl::Parts { path, query, .. } => (format!("/{}", path.join("/")), query),
+    };
+
+    let raw = match raw.starts_with("{{") {
+        true => match raw.find("}}") {
+            Some(end) => raw[end + 2..].to_string(),
+            None => raw,
+        },
+        false => match Url::parse(&raw) {
+            Ok(url) => match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            },
+            Err(_) => raw,
+        },
+    };
+
+    let (path, query) = match raw.split_once('?') {
+        Some((path, query)) => (path.to_string(), Param::parse(query)),
+        None => (raw, vec![]),
+    };
+
+    match parts.is_empty() {
+        true => (path, query),
+        false => {
+            let params = parts
+                .into_iter()
+                .map(|p| Param {
+                    enabled: !p.disabled,
+                    ..Param::new(&p.key, &p.value)
+                })
+                .collect();
+            (path, params)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "info": { "name": "utsjekk", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+        "item": [
+            {
+                "name": "iverksetting",
+                "item": [
+                    {
+                        "name": "Start",
+                        "request": {
+                            "method": "POST",
+                            "header": [
+                                { "key": "Accept", "value": "application/json" },
+                                { "key": "X-Debug", "value": "1", "disabled": true }
+                            ],
+                            "body": { "mode": "raw", "raw": "{ \"sakId\": \"{{sakId}}\" }" },
+                            "url": { "raw": "{{baseUrl}}/api/iverksetting?dry=true", "host": ["{{baseUrl}}"] },
+                            "description": { "content": "Starter en iverksetting" }
+                        }
+                    }
+                ]
+            },
+            { "name": "Status", "request": { "method": "GET", "url": "https://utsjekk.intern.dev.nav.no/status" } },
+            {
+                "name": "Vedlegg",
+                "request": {
+                    "method": "POST",
+                    "body": { "mode": "formdata", "formdata": [
+                        { "key": "sakId", "value": "1234", "type": "text" },
+                        { "key": "fil", "src": ["./vedlegg.pdf"], "type": "file" }
+                    ] },
+                    "url": "{{baseUrl}}/api/vedlegg"
+                }
+            },
+            { "name": "Options", "request": { "method": "OPTIONS", "url": "{{baseUrl}}/" } }
+        ],
+        "variable": [{ "key": "sakId", "value": "1234" }]
+    }"#;
+
+    #[test]
+    fn import_collection() {
+        let import = import(COLLECTION).unwrap();
+
+        assert_eq!(import.requests.len(), 3);
+        assert_eq!(import.vars.get("sakId").map(String::as_str), Some("1234"));
+        assert_eq!(import.skipped.len(), 1);
+
+        let start = &import.requests[0];
+        assert_eq!(start.folder, "iverksetting");
+        assert_eq!(start.path, "/api/iverksetting");
+        assert_eq!(start.query, vec![Param::new("dry", "true")]);
+        assert_eq!(start.desc, "Starter en iverksetting");
+        assert_eq!(start.headers.len(), 2);
+        assert!(!start.headers[1].enabled);
+
+        let status = &import.requests[1];
+        assert_eq!(status.path, "/status");
+        assert_eq!(status.desc, "Status");
+
+        let vedlegg = &import.requests[2];
+        assert_eq!(vedlegg.body_mode, BodyMode::Multipart);
+        assert_eq!(vedlegg.form[1].file(), Some("./vedlegg.pdf"));
+    }
+
+    #[test]
+    fn export_roundtrips() {
+        let mut app = App::named("utsjekk");
+        app.hosts = vec!["utsjekk.intern.dev.nav.no".into()];
+        app.requests = import(COLLECTION).unwrap().requests;
+
+        let mut exported = import(&export(&app).unwrap()).unwrap();
+        for (exported, req) in exported.requests.iter_mut().zip(&app.requests) {
+            exported.created = req.created;
+            exported.modified = req.modified;
+        }
+
+        assert_eq!(exported.requests, app.requests);
+        assert_eq!(
+            exported.vars.get(BASE_URL).map(String::as_str),
+            Some("https://utsjekk.intern.dev.nav.no")
+        );
+    }
+}
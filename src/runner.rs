@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::{
+    client::Response,
+    state::{App, Assert, Request, Sample},
+    vars,
+};
+
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub assert: Assert,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Case {
+    pub request: Request,
+    pub checks: Vec<Check>,
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl Case {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.checks.iter().all(|c| c.passed)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub app: String,
+    pub tag: Option<String>,
+    pub cases: Vec<Case>,
+}
+
+/// Sends the requests of the app in order, optionally only those tagged with `tag`.
+/// Extraction rules are applied between requests, so chained requests see earlier values.
+pub fn run(app: &mut App, tag: Option<&str>) -> Report {
+    let requests = app
+        .ordered()
+        .into_iter()
+        .filter(|r| tag.is_none_or(|tag| r.tags.contains(tag)))
+        .collect::<Vec<_>>();
+
+    let cases = requests
+        .into_iter()
+        .map(|request| match app.send(&request) {
+            Ok(res) => {
+                app.extract(&request, &res);
+                app.record(&request.id, Sample::new(&res));
+                Case {
+                    checks: request.asserts.iter().map(|a| check(a, &res)).collect(),
+                    error: None,
+                    elapsed: res.elapsed,
+                    request,
+                }
+            }
+            Err(err) => Case {
+                request,
+                checks: vec![],
+                error: Some(format!("{err:#}")),
+                elapsed: Duration::ZERO,
+            },
+        })
+        .collect();
+
+    Report {
+        app: app.name.clone(),
+        tag: tag.map(String::from),
+        cases,
+    }
+}
+
+pub fn check(assert: &Assert, res: &Response) -> Check {
+    let (passed, message) = match assert {
+        Assert::Status(expected) => (
+            res.status == *expected,
+            format!("expected status {expected}, got {}", res.status),
+        ),
+        Assert::JsonEquals(path, expected) => match vars::query(path, &res.body) {
+            Some(actual) => (
+                &actual == expected,
+                format!("expected {path} == {expected}, got {actual}"),
+            ),
+            None => (false, format!("{path} not found in body")),
+        },
+        Assert::JsonMatches(path, pattern) => {
+            match (Regex::new(pattern), vars::query(path, &res.body)) {
+                (Err(err), _) => (false, format!("invalid regex {pattern}: {err}")),
+                (_, None) => (false, format!("{path} not found in body")),
+                (Ok(re), Some(actual)) => (
+                    re.is_match(&actual),
+                    format!("expected {path} ~ {pattern}, got {actual}"),
+                ),
+            }
+        }
+        Assert::Header(name) => (
+            res.headers
+                .iter()
+                .any(|(k, _)| k.eq_ignore_ascii_case(name)),
+            format!("expected header {name}"),
+        ),
+        Assert::TimeUnder(ms) => (
+            res.elapsed.as_millis() < *ms as u128,
+            format!(
+                "expected under {ms} ms, took {} ms",
+                res.elapsed.as_millis()
+            ),
+        ),
+    };
+
+    Check {
+        assert: assert.clone(),
+        passed,
+        message,
+    }
+}
+
+impl Report {
+    pub fn failures(&self) -> usize {
+        self.cases.iter().filter(|c| !c.passed()).count()
+    }
+
+    pub fn print(&self) {
+        for case in &self.cases {
+            let result = match case.passed() {
+                true => "PASS",
+                false => "FAIL",
+            };
+            println!(
+                "{result} {} {} ({} ms)",
+                case.request.method,
+                case.request.path,
+                case.elapsed.as_millis()
+            );
+
+            if let Some(err) = &case.error {
+                println!("     {err}");
+            }
+
+            for check in case.checks.iter().filter(|c| !c.passed) {
+                println!("     {}: {}", check.assert, check.message);
+            }
+        }
+
+        println!(
+            "\n{} requests, {} passed, {} failed",
+            self.cases.len(),
+            self.cases.len() - self.failures(),
+            self.failures()
+        );
+    }
+
+    pub fn junit(&self) -> String {
+        let errors = self.cases.iter().filter(|c| c.error.is_some()).count();
+        let failures = self.failures() - errors;
+        let time = self
+            .cases
+            .iter()
+            .fold(0.0, |sum, c| sum + c.elapsed.as_secs_f64());
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">\n",
+            self.cases.len(),
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">\n",
+            escape(&self.app),
+            self.cases.len(),
+        ));
+
+        for case in &self.cases {
+            let name = format!("{} {}", case.request.method, case.request.path);
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+                escape(&name),
+                escape(&self.app),
+                case.elapsed.as_secs_f64(),
+            ));
+
+            if let Some(err) = &case.error {
+                xml.push_str(&format!("      <error message=\"{}\"/>\n", escape(err)));
+            }
+
+            for check in case.checks.iter().filter(|c| !c.passed) {
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\" type=\"{}\"/>\n",
+                    escape(&check.message),
+                    escape(&check.assert.to_string()),
+                ));
+            }
+
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: body.into(),
+            bytes: body.into(),
+            elapsed: Duration::from_millis(120),
+            timing: Default::default(),
+            sizes: Default::default(),
+        }
+    }
+
+    #[test]
+    fn check_assertions() {
+        let res = response(200, r#"{ "status": "OK", "id": 42 }"#);
+
+        assert!(check(&Assert::Status(200), &res).passed);
+        assert!(!check(&Assert::Status(201), &res).passed);
+        assert!(check(&Assert::JsonEquals("$.status".into(), "OK".into()), &res).passed);
+        assert!(check(&Assert::JsonMatches("$.id".into(), r"^\d+$".into()), &res).passed);
+        assert!(check(&Assert::Header("content-type".into()), &res).passed);
+        assert!(!check(&Assert::TimeUnder(100), &res).passed);
+    }
+
+    #[test]
+    fn junit_reports_failures() {
+        let request = Request {
+            path: "/status?a=1&b=<2>".into(),
+            ..Default::default()
+        };
+        let res = response(500, "");
+        let report = Report {
+            app: "utsjekk".into(),
+            tag: None,
+            cases: vec![Case {
+                checks: vec![check(&Assert::Status(200), &res)],
+                error: None,
+                elapsed: res.elapsed,
+                request,
+            }],
+        };
+
+        let xml = report.junit();
+        assert!(xml.contains("failures=\"1\""));
+        assert!(xml.contains("name=\"GET /status?a=1&amp;b=&lt;2&gt;\""));
+        assert!(xml.contains("<failure message=\"expected status 200, got 500\""));
+    }
+}
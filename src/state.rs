@@ -1,48 +1,212 @@
 use std::{
-    collections::{BTreeMap, BTreeSet}, fmt::Display, fs::OpenOptions, hash::{DefaultHasher, Hash, Hasher}, io::Write, os::unix::fs::OpenOptionsExt
+    collections::{BTreeMap, BTreeSet}, fmt::Display, fs::{self, DirBuilder, File, OpenOptions, Permissions}, io::Write, os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt}, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{Duration, SystemTime, UNIX_EPOCH}
 };
 
+use color_eyre::eyre::{eyre, Context};
 use futures::executor::block_on;
 use k8s_openapi::api::{
     core::v1::{Pod, Secret},
     networking::v1::Ingress,
 };
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 
 use crate::{
+    client::{self, Client, Progress, Response},
+    config::Config,
+    merge::{self, Snapshot},
+    migrate,
     k8s::{self, Nais, NaisEnv, NaisIng},
-    NAMESPACE,
+    load::{self, Plan, Stats, Summary},
+    util::{millis, number},
+    vars, workspace, NAMESPACE,
 };
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct State {
+    /// The schema version, older files are migrated when loaded.
+    #[serde(default)]
+    version: u64,
     apps: BTreeMap<String, App>,
+    /// Fields written by newer versions, kept so saving doesn't drop them. Apps and requests
+    /// keep theirs the same way.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+    /// Where the state is loaded from and saved to.
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    backups: usize,
+    /// The repository's shared requests, see `workspace`.
+    #[serde(skip)]
+    workspace: Option<PathBuf>,
+    /// The file as last read or written, to merge what other instances save, see `merge`.
+    #[serde(skip)]
+    synced: Snapshot,
+    #[serde(skip)]
+    stamp: Option<Stamp>,
+    /// The apps as the views last saw them, once changes from other instances or the
+    /// workspace were merged in since.
+    #[serde(skip)]
+    unseen: Option<Snapshot>,
+    /// The ids of the shared requests with a file as last loaded or saved, see `workspace::save`.
+    #[serde(skip)]
+    shared: BTreeSet<String>,
+    /// What went wrong loading or saving that didn't stop it, for the views to show.
+    #[serde(skip)]
+    problems: Vec<String>,
+}
+
+/// The modified time, inode and length of the state file.
+type Stamp = (SystemTime, u64, u64);
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            version: migrate::VERSION,
+            apps: BTreeMap::new(),
+            extra: BTreeMap::new(),
+            path: PathBuf::new(),
+            backups: 0,
+            workspace: None,
+            synced: Snapshot::new(),
+            stamp: None,
+            unseen: None,
+            shared: BTreeSet::new(),
+            problems: vec![],
+        }
+    }
 }
 
 impl State {
-    pub fn save(&self) {
-        let path = std::env::current_dir()
-            .expect("Failed to get current directory")
-            .join("state.json");
+    /// Writes to a temporary file that is fsynced and renamed over the state, so a crash mid-write
+    /// leaves the previous version intact. The file is only readable by the user, as it holds
+    /// tokens and secrets. Another instance may have saved since, its changes are merged in
+    /// first while holding a lock, so neither overwrites the other.
+    pub fn save(&mut self) {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .expect("Failed to create state directory");
+
+        let _lock = self.lock();
+        self.merge_from_disk();
+        self.save_workspace();
+        self.backup();
+
+        let state = serde_json::to_string(&self).unwrap();
+        write_private(&self.path, state.as_bytes()).expect("Failed to write state file");
+        self.synced = merge::snapshot(self);
+        self.stamp = self.disk_stamp();
+    }
 
+    fn save_workspace(&mut self) {
+        let Some(root) = self.workspace.clone() else {
+            return;
+        };
+        let before = merge::snapshot(self);
+        let mut seen = std::mem::take(&mut self.shared);
+        match workspace::save(&root, self, &mut seen) {
+            Ok(true) => {
+                self.unseen.get_or_insert(before);
+            }
+            Ok(false) => {}
+            Err(err) => self.problems.push(format!("{err:#}")),
+        }
+        self.shared = seen;
+    }
+
+    /// Takes what went wrong since last asked, like shared requests that couldn't be written.
+    pub fn problems(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.problems)
+    }
+
+    /// Merges in what another instance saved since the file was last read or written. Called
+    /// every pass of the event loop, where a `stat` of the file is cheap enough that it is
+    /// polled rather than watched. When there are changes the views haven't seen yet, here or
+    /// when saving, returns the apps as the views last saw them to merge their edits with.
+    pub fn sync(&mut self) -> Option<Snapshot> {
+        if self.disk_stamp() != self.stamp {
+            let _lock = self.lock();
+            self.merge_from_disk();
+        }
+        self.unseen.take()
+    }
+
+    fn merge_from_disk(&mut self) {
+        let stamp = self.disk_stamp();
+        if stamp == self.stamp {
+            return;
+        }
+        let Ok(json) = fs::read_to_string(&self.path) else {
+            return;
+        };
+        // a file this build can't read is left to the instance that wrote it
+        let Ok(disk) = State::parse(&json, &self.path) else {
+            return;
+        };
+
+        let before = merge::snapshot(self);
+        let base = std::mem::replace(&mut self.synced, merge::snapshot(&disk));
+        if merge::merge(self, &disk, &base) {
+            self.unseen.get_or_insert(before);
+        }
+        self.stamp = stamp;
+    }
+
+    /// Every save replaces the file, so a new inode tells it apart even within the resolution
+    /// of the modified time.
+    fn disk_stamp(&self) -> Option<Stamp> {
+        let meta = fs::metadata(&self.path).ok()?;
+        Some((meta.modified().ok()?, meta.ino(), meta.len()))
+    }
+
+    /// Holds `state.json.lock` until dropped, so instances save one at a time.
+    fn lock(&self) -> Option<File> {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".lock");
         let file = OpenOptions::new()
-            .mode(0o777)
+            .mode(0o600)
             .write(true)
-            .truncate(true)
-            .open(&path);
+            .create(true)
+            .truncate(false)
+            .open(name)
+            .ok()?;
+        file.lock().ok()?;
+        Some(file)
+    }
 
-        let mut file = match file {
-            Ok(file) => file,
-            Err(_) => OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(&path)
-                .expect("Failed to edit file"),
-        };
+    /// The workspace of shared requests, created in the working directory if there is none.
+    pub fn workspace(&mut self) -> &Path {
+        self.workspace
+            .get_or_insert_with(|| PathBuf::from(workspace::DIR))
+    }
 
-        let state = serde_json::to_string(&self).unwrap();
-        file.write_all(state.as_bytes())
-            .expect("Failed to write to file");
+    /// Keeps the current version as `state.json.1`, shifting older versions up to `backups`.
+    fn backup(&self) {
+        if self.backups == 0 || !self.path.exists() {
+            return;
+        }
+
+        let backup = |n| self.backup_path(n);
+        for n in (1..self.backups).rev() {
+            let _ = fs::rename(backup(n), backup(n + 1));
+        }
+        if fs::copy(&self.path, backup(1)).is_ok() {
+            let _ = fs::set_permissions(backup(1), Permissions::from_mode(0o600));
+        }
+    }
+
+    /// The n-th previous version, e.g. `state.json.1`.
+    fn backup_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
     }
 
     pub fn insert(&mut self, app: App) {
@@ -69,15 +233,80 @@ impl State {
         self.apps.values().collect()
     }
 
-    pub fn load() -> Self {
-        match std::fs::read_to_string("state.json") {
-            Ok(state) => serde_json::from_str(&state).expect(""),
+    /// Loads the state from `--state`, the config or the data directory. A `state.json` in the
+    /// working directory, where it used to be kept, is picked up when there is no state yet.
+    pub fn load() -> color_eyre::Result<Self> {
+        let config = Config::load()?;
+        let path = config.state_path();
+        let legacy = Path::new("state.json");
+        let source = match config.state_override() {
+            None if !path.exists() && legacy.exists() => legacy.to_path_buf(),
+            _ => path.clone(),
+        };
+
+        let mut state = match std::fs::read_to_string(&source) {
+            Ok(json) => State::parse(&json, &source)?,
             Err(_) => State::default(),
+        };
+        state.path = path;
+        state.backups = config.backups;
+        state.scrub_secrets(&source);
+        state.synced = merge::snapshot(&state);
+        state.stamp = state.disk_stamp();
+
+        state.workspace = workspace::find();
+        if let Some(root) = state.workspace.clone() {
+            state.shared = workspace::ids(&root);
+            match workspace::load(&root, &mut state) {
+                Ok(skipped) => state.problems.extend(skipped),
+                Err(err) => state.problems.push(format!("{err:#}")),
+            }
+        }
+        Ok(state)
+    }
+
+    /// Reads the state file, migrating it to the current version. The file is written to
+    /// `state.json.v<version>` before it is migrated, without the secrets, so it is kept if the
+    /// migration fails.
+    fn parse(json: &str, source: &Path) -> color_eyre::Result<Self> {
+        let name = source.display();
+        let mut doc = serde_json::from_str::<serde_json::Value>(json)
+            .wrap_err_with(|| format!("{name} is not valid JSON"))?;
+
+        let version = migrate::version(&doc);
+        if version < migrate::VERSION {
+            let backup = source.with_extension(format!("json.v{version}"));
+            write_backup(&backup, &doc)
+                .wrap_err_with(|| format!("failed to back up {name} before upgrading it"))?;
+            migrate::migrate(&mut doc).wrap_err_with(|| {
+                format!("failed to upgrade {name}, the original is kept as {}", backup.display())
+            })?;
+        } else {
+            migrate::migrate(&mut doc).wrap_err_with(|| format!("failed to read {name}"))?;
+        }
+
+        serde_json::from_value(doc).wrap_err_with(|| format!("failed to read {name}"))
+    }
+
+    /// Earlier versions saved the client secrets in plaintext. They are removed from the state,
+    /// its backups and the file it was loaded from, which are replaced by files only the user
+    /// can read. Files that can't be replaced are reported.
+    fn scrub_secrets(&mut self, source: &Path) {
+        let backups = (1..=self.backups).map(|n| self.backup_path(n));
+
+        for path in std::iter::once(source.to_path_buf()).chain(backups).collect::<Vec<_>>() {
+            if let Some(json) = fs::read_to_string(&path).ok().and_then(|json| scrub(&json)) {
+                if let Err(err) = write_private(&path, json.as_bytes()) {
+                    let path = path.display();
+                    self.problems
+                        .push(format!("failed to remove secrets from {path}: {err}"));
+                }
+            }
         }
     }
 
     async fn fetch_pods(&self) -> Vec<Pod> {
-        k8s::pods(NAMESPACE)
+        k8s::pods(&k8s::namespace())
             .await
             .expect("Failed to fetch k8s pods")
     }
@@ -105,6 +334,46 @@ impl State {
     }
 }
 
+/// Writes the document without its secrets, readable only by the user.
+fn write_backup(path: &Path, doc: &serde_json::Value) -> std::io::Result<()> {
+    let mut doc = doc.clone();
+    migrate::remove_secrets(&mut doc);
+    write_private(path, doc.to_string().as_bytes())
+}
+
+/// Writes to a temporary file that is fsynced and renamed over the file, so a crash mid-write
+/// leaves the previous version intact. The file is only readable by the user.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = OpenOptions::new()
+        .mode(0o600)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp, path)?;
+    // the rename is only durable once the directory is synced too
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// The state file without the `client_secret` of every app, if it had any.
+fn scrub(json: &str) -> Option<String> {
+    let mut state = serde_json::from_str::<serde_json::Value>(json).ok()?;
+    migrate::remove_secrets(&mut state).then(|| state.to_string())
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct App {
     pub name: String,
@@ -112,8 +381,22 @@ pub struct App {
     pub cluster: String,
     pub ns: String,
     pub hosts: Vec<String>,
+    /// The host picked in the hosts view, see `App::host`.
+    #[serde(skip)]
+    pub host: Option<String>,
     pub azure: NaisOAuth2,
-    pub requests: BTreeSet<Request>,
+    pub requests: Vec<Request>,
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// The app's OpenAPI document, stored when discovering endpoints and used to validate bodies.
+    #[serde(default)]
+    pub openapi: Option<serde_json::Value>,
+    /// Defaults for the requests of the app, each setting can be overridden per request.
+    #[serde(default)]
+    pub settings: Settings,
+    /// See `State::extra`.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 impl App {
@@ -127,15 +410,251 @@ impl App {
             pod: p.metadata.name.clone().unwrap_or("???".into()),
             cluster: envs.get_env("NAIS_CLUSTER_NAME").unwrap_or_default(),
             hosts: vec![],
-            requests: BTreeSet::new(),
+            host: None,
+            requests: vec![],
+            vars: BTreeMap::new(),
+            openapi: None,
+            settings: Settings::default(),
+            extra: BTreeMap::new(),
             name,
             ns,
         }
     }
 
+    /// An app known only by name, e.g. from the shared requests. The pod and hosts are filled in
+    /// when the apps are refreshed from k8s.
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            pod: "".into(),
+            cluster: "".into(),
+            ns: NAMESPACE.into(),
+            hosts: vec![],
+            host: None,
+            azure: NaisOAuth2::new(name, NAMESPACE),
+            requests: vec![],
+            vars: BTreeMap::new(),
+            openapi: None,
+            settings: Settings::default(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// The host to send to, the one picked in the hosts view or else the first.
+    pub fn host(&self) -> Option<&str> {
+        let picked = self
+            .host
+            .as_deref()
+            .filter(|h| self.hosts.iter().any(|x| x == h));
+        picked.or(self.hosts.first().map(String::as_str))
+    }
+
     pub fn add_request(&mut self, request: Request) {
-        self.requests.retain(|r| r.id != request.id);
-        self.requests.insert(request);
+        match self.requests.iter_mut().find(|r| r.id == request.id) {
+            Some(existing) => *existing = request,
+            None => self.requests.push(request),
+        }
+    }
+
+    /// Adds a copy of the request right after it, with a new id and without its history.
+    pub fn duplicate_request(&mut self, id: &str) -> Option<Request> {
+        let idx = self.requests.iter().position(|r| r.id == id)?;
+        let copy = Request {
+            history: vec![],
+            shared: false,
+            ..Request::new_from(&self.requests[idx])
+        };
+        self.requests.insert(idx + 1, copy.clone());
+        Some(copy)
+    }
+
+    pub fn remove_request(&mut self, id: &str) -> Option<Request> {
+        let idx = self.requests.iter().position(|r| r.id == id)?;
+        Some(self.requests.remove(idx))
+    }
+
+    /// The requests grouped by their folder path. Folders and requests keep the order they
+    /// first appear in `requests`.
+    pub fn tree(&self) -> Vec<Node> {
+        let mut nodes = vec![];
+        for request in &self.requests {
+            Node::insert(&mut nodes, &request.folders(), request.clone());
+        }
+        nodes
+    }
+
+    /// The requests in the order they are shown in the tree.
+    pub fn ordered(&self) -> Vec<Request> {
+        Node::flatten(self.tree())
+    }
+
+    /// Moves a request up (negative) or down (positive) among its siblings.
+    pub fn move_request(&mut self, id: &str, offset: isize) {
+        let Some(request) = self.requests.iter().find(|r| r.id == id) else {
+            return;
+        };
+
+        let parents = request
+            .folders()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let moved = self.move_node(
+            &parents,
+            offset,
+            |node| matches!(node, Node::Request(r) if r.id == id),
+        );
+        if moved {
+            self.requests
+                .iter_mut()
+                .filter(|r| r.id == id)
+                .for_each(Request::touch);
+        }
+    }
+
+    /// Moves a folder, with everything in it, up (negative) or down (positive) among its siblings.
+    pub fn move_folder(&mut self, path: &[String], offset: isize) {
+        let Some((name, parents)) = path.split_last() else {
+            return;
+        };
+
+        self.move_node(
+            parents,
+            offset,
+            |node| matches!(node, Node::Folder(f, _) if f == name),
+        );
+    }
+
+    /// Returns whether the node moved, it doesn't past the first or last of its siblings.
+    fn move_node(
+        &mut self,
+        parents: &[String],
+        offset: isize,
+        is_target: impl Fn(&Node) -> bool,
+    ) -> bool {
+        let mut tree = self.tree();
+        let mut siblings = &mut tree;
+
+        for parent in parents {
+            let children = siblings.iter_mut().find_map(|node| match node {
+                Node::Folder(folder, children) if folder == parent => Some(children),
+                _ => None,
+            });
+
+            match children {
+                Some(children) => siblings = children,
+                None => return false,
+            }
+        }
+
+        let Some(idx) = siblings.iter().position(is_target) else {
+            return false;
+        };
+        let to = idx.saturating_add_signed(offset).min(siblings.len() - 1);
+        siblings.swap(idx, to);
+
+        self.requests = Node::flatten(tree);
+        idx != to
+    }
+
+    /// Sends the request to the host of the app, with `{{var}}` placeholders resolved.
+    pub fn send(&mut self, request: &Request) -> color_eyre::Result<Response> {
+        let (client, request) = self.client(request)?;
+        block_on(client.send(&request))
+    }
+
+    /// Sends the request in the background, timed, it is cancelled by aborting the task.
+    pub fn spawn(
+        &mut self,
+        request: &Request,
+    ) -> color_eyre::Result<JoinHandle<color_eyre::Result<Response>>> {
+        let (client, request) = self.client(request)?;
+        Ok(tokio::spawn(async move { client.send_timed(&request).await }))
+    }
+
+    /// A client for the host of the app with the request's settings, and the request with its variables resolved.
+    fn client(&mut self, request: &Request) -> color_eyre::Result<(Client, Request)> {
+        let token = self.token()?;
+        let host = self
+            .host()
+            .ok_or_else(|| eyre!("{} has no hosts", self.name))?;
+
+        let settings = request.settings.or(&self.settings);
+        Ok((
+            Client::new(host, token, settings),
+            vars::resolve(request, &self.vars),
+        ))
+    }
+
+    /// Starts streaming the response of the request into the file, in the background.
+    /// The returned progress is updated as chunks arrive and marked done when it finishes.
+    pub fn download(
+        &mut self,
+        request: &Request,
+        path: PathBuf,
+    ) -> color_eyre::Result<(Arc<Mutex<Progress>>, JoinHandle<()>)> {
+        let (client, request) = self.client(request)?;
+        let progress = Arc::new(Mutex::new(Progress {
+            path: path.clone(),
+            ..Progress::default()
+        }));
+
+        let shared = progress.clone();
+        let task = tokio::spawn(async move {
+            let result = client.download(&request, &path, shared.clone()).await;
+            let mut shared = shared.lock().expect("download progress");
+            shared.done = Some(result.map_err(|e| format!("{e:#}")));
+        });
+
+        Ok((progress, task))
+    }
+
+    /// Fetches an Azure token for the app, reading the client credentials from k8s if missing.
+    pub fn token(&mut self) -> color_eyre::Result<String> {
+        if self.azure.client_secret.is_empty() {
+            self.azure.update()?;
+        }
+
+        block_on(client::token(&self.name, &self.ns, self.azure.clone()))
+    }
+
+    /// Applies the extraction rules of the request and stores the matches as app variables.
+    pub fn extract(
+        &mut self,
+        request: &Request,
+        response: &Response,
+    ) -> Vec<(String, Option<String>)> {
+        let extracted = vars::extract(&request.extract, response);
+        for (var, value) in &extracted {
+            if let Some(value) = value {
+                self.vars.insert(var.clone(), value.clone());
+            }
+        }
+        extracted
+    }
+
+    /// Starts sending the request according to the plan, in the background.
+    /// The returned stats are updated as responses arrive and marked done when it finishes.
+    pub fn load(
+        &mut self,
+        request: &Request,
+        plan: Plan,
+    ) -> color_eyre::Result<(Arc<Mutex<Stats>>, JoinHandle<()>)> {
+        let (client, request) = self.client(request)?;
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        let task = tokio::spawn(load::run(client, request, plan, stats.clone()));
+        Ok((stats, task))
+    }
+
+    /// Adds the sample to the history of the request, dropping the oldest beyond `HISTORY`.
+    pub fn record(&mut self, id: &str, sample: Sample) {
+        let Some(request) = self.requests.iter_mut().find(|r| r.id == id) else {
+            return;
+        };
+
+        request.history.push(sample);
+        let excess = request.history.len().saturating_sub(HISTORY);
+        request.history.drain(..excess);
     }
 
     pub fn update_hosts(&mut self) {
@@ -156,6 +675,8 @@ pub struct NaisOAuth2 {
     name: String,
     ns: String,
     pub client_id: String,
+    /// Only kept in memory, it is read from k8s again when a token is needed.
+    #[serde(default, skip_serializing)]
     pub client_secret: String,
     pub token_endpoint: String,
 }
@@ -169,46 +690,168 @@ impl NaisOAuth2 {
         }
     }
 
-    async fn fetch_secret(&self) -> Secret {
+    async fn fetch_secret(&self) -> color_eyre::Result<Secret> {
         k8s::secret(&self.name, &self.ns)
             .await
-            .expect("Failed to fetch k8s azure secret")
+            .wrap_err_with(|| format!("failed to fetch the azure secret of {}", self.name))
     }
 
-    pub fn update(&mut self) {
-        let secret = k8s::decode(block_on(self.fetch_secret()));
-        self.client_id = secret["AZURE_APP_CLIENT_ID"].to_string();
-        self.client_secret = secret["AZURE_APP_CLIENT_SECRET"].to_string();
-        self.token_endpoint = secret["AZURE_OPENID_CONFIG_TOKEN_ENDPOINT"].to_string();
+    /// Reads the client credentials from the app's azure secret in k8s.
+    pub fn update(&mut self) -> color_eyre::Result<()> {
+        let secret = k8s::decode(block_on(self.fetch_secret())?);
+        let key = |key: &str| {
+            secret
+                .get(key)
+                .cloned()
+                .ok_or_else(|| eyre!("the azure secret of {} has no {key}", self.name))
+        };
+        self.client_id = key("AZURE_APP_CLIENT_ID")?;
+        self.client_secret = key("AZURE_APP_CLIENT_SECRET")?;
+        self.token_endpoint = key("AZURE_OPENID_CONFIG_TOKEN_ENDPOINT")?;
+        Ok(())
     }
 }
 
-#[derive(Default, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct Request {
-    pub id: u64,
+    /// A random UUID, kept for the life of the request.
+    pub id: String,
+    /// Seconds since the Unix epoch, zero if unknown.
+    #[serde(default)]
+    pub created: u64,
+    /// Milliseconds since the Unix epoch, which copy of the request wins a merge.
+    #[serde(default)]
+    pub modified: u64,
     pub method: Method,
     pub path: String,
+    #[serde(default)]
+    pub query: Vec<Param>,
     pub desc: String,
-    pub headers: BTreeSet<Header>,
+    /// Folder path in the request tree, with `/` between nested folders.
+    #[serde(default)]
+    pub folder: String,
+    /// In the order they are sent, duplicate keys are allowed.
+    pub headers: Vec<Header>,
+    /// The JSON or raw text, or the path of the file to send in file mode.
     pub body: String,
+    #[serde(default)]
+    pub body_mode: BodyMode,
+    /// The fields of a form or multipart body, where a value like `@path` is a file part.
+    #[serde(default)]
+    pub form: Vec<Param>,
+    #[serde(default)]
+    pub extract: Vec<Extract>,
+    #[serde(default)]
+    pub asserts: Vec<Assert>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// The OpenAPI operation the request was generated from.
+    #[serde(default)]
+    pub operation: Option<Operation>,
+    /// Overrides of the app's settings.
+    #[serde(default)]
+    pub settings: Settings,
+    /// The latest responses, oldest first.
+    #[serde(default)]
+    pub history: Vec<Sample>,
+    /// Also kept in the repository's workspace, to share with the team.
+    #[serde(default)]
+    pub shared: bool,
+    /// See `State::extra`.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 impl Request {
     pub fn new(method: Method, path: &str, headers: Vec<Header>, body: &str) -> Self {
-        let mut hasher = DefaultHasher::new();
-        method.hash(&mut hasher);
-        path.hash(&mut hasher);
-        body.hash(&mut hasher);
-
         Self {
-            id: hasher.finish(),
+            id: new_id(),
+            created: now(),
+            modified: now_millis(),
             method,
             path: path.into(),
+            query: vec![],
             desc: "".into(),
-            headers: headers.into_iter().collect(),
+            folder: "".into(),
+            headers,
             body: body.into(),
+            body_mode: BodyMode::Json,
+            form: vec![],
+            extract: vec![],
+            asserts: vec![],
+            tags: BTreeSet::new(),
+            operation: None,
+            settings: Settings::default(),
+            history: vec![],
+            shared: false,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// A copy of the request with a new id, created now.
+    pub fn new_from(request: &Request) -> Self {
+        Self {
+            id: new_id(),
+            created: now(),
+            modified: now_millis(),
+            ..request.clone()
+        }
+    }
+
+    /// Marks the request as modified now, and later than it was even if the clock is behind.
+    pub fn touch(&mut self) {
+        self.modified = now_millis().max(self.modified + 1);
+    }
+
+    /// The path with the enabled query params appended, URL encoded.
+    pub fn path_with_query(&self) -> String {
+        match Param::encode(&self.query) {
+            query if query.is_empty() => self.path.clone(),
+            query if self.path.contains('?') => format!("{}&{query}", self.path),
+            query => format!("{}?{query}", self.path),
+        }
+    }
+
+    /// The explicit Content-Type header, or the one given by the body mode.
+    pub fn content_type(&self) -> Option<String> {
+        self.headers
+            .iter()
+            .find(|h| h.enabled && h.key.eq_ignore_ascii_case("content-type"))
+            .map(|h| h.value.clone())
+            .or_else(|| self.body_mode.content_type().map(String::from))
+    }
+
+    /// Whether a body is sent at all, GET requests never have one.
+    pub fn has_body(&self) -> bool {
+        self.method != Method::Get
+            && match self.body_mode {
+                BodyMode::Json | BodyMode::Raw | BodyMode::File => !self.body.is_empty(),
+                BodyMode::Form | BodyMode::Multipart => self.form.iter().any(|p| p.enabled),
+            }
+    }
+
+    /// Whether an enabled header sets the key, which then replaces what would be added for it.
+    pub fn has_header(&self, key: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|h| h.enabled && h.key.eq_ignore_ascii_case(key))
+    }
+
+    /// Moves a query string typed into the path over to the query params.
+    pub fn split_query(&mut self) {
+        if let Some((path, query)) = self.path.clone().split_once('?') {
+            self.path = path.into();
+            self.query.extend(Param::parse(query));
         }
     }
+
+    pub fn folders(&self) -> Vec<&str> {
+        self.folder
+            .split('/')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .collect()
+    }
 }
 
 #[derive(Default, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug, Hash)]
@@ -221,6 +864,13 @@ pub enum Method {
     Delete,
 }
 
+impl Method {
+    /// Whether sending the request twice has the same effect as sending it once, so it is safe to retry.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(self, Method::Get | Method::Put | Method::Delete)
+    }
+}
+
 impl Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -233,14 +883,737 @@ impl Display for Method {
     }
 }
 
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BodyMode {
+    #[default]
+    Json,
+    Raw,
+    Form,
+    Multipart,
+    File,
+}
+
+impl BodyMode {
+    pub fn next(self) -> Self {
+        match self {
+            BodyMode::Json => BodyMode::Raw,
+            BodyMode::Raw => BodyMode::Form,
+            BodyMode::Form => BodyMode::Multipart,
+            BodyMode::Multipart => BodyMode::File,
+            BodyMode::File => BodyMode::Json,
+        }
+    }
+
+    /// Multipart has none, since the boundary is chosen when the body is built.
+    pub fn content_type(&self) -> Option<&str> {
+        match self {
+            BodyMode::Json => Some("application/json"),
+            BodyMode::Raw => Some("text/plain"),
+            BodyMode::Form => Some("application/x-www-form-urlencoded"),
+            BodyMode::Multipart => None,
+            BodyMode::File => Some("application/octet-stream"),
+        }
+    }
+}
+
+impl Display for BodyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyMode::Json => write!(f, "json"),
+            BodyMode::Raw => write!(f, "raw"),
+            BodyMode::Form => write!(f, "form"),
+            BodyMode::Multipart => write!(f, "multipart"),
+            BodyMode::File => write!(f, "file"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug, Default)]
 pub struct Header {
     pub key: String,
     pub value: String,
+    /// Disabled headers are kept but not sent.
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    /// Secret values are masked in the UI and left as placeholders in exports.
+    #[serde(default)]
+    pub secret: bool,
 }
 
 impl Header {
     pub fn new(key: String, value: String) -> Self {
-        Self { key, value }
+        Self {
+            key,
+            value,
+            enabled: true,
+            secret: false,
+        }
+    }
+
+    /// Whether the value is secret, flagged so or a credential like `Authorization`, `Cookie`,
+    /// `Proxy-Authorization` or `X-Api-Key`.
+    pub fn is_secret(&self) -> bool {
+        let key = self.key.to_ascii_lowercase();
+        self.secret
+            || matches!(
+                key.as_str(),
+                "authorization" | "cookie" | "proxy-authorization"
+            )
+            || key.ends_with("-api-key")
+    }
+}
+
+/// A query param, disabled params are kept but not sent.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Param {
+    pub key: String,
+    pub value: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Param {
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            enabled: true,
+        }
+    }
+
+    /// Decodes a query string like `a=1&b=two%20words`.
+    pub fn parse(query: &str) -> Vec<Param> {
+        url::form_urlencoded::parse(query.as_bytes())
+            .map(|(key, value)| Param::new(&key, &value))
+            .collect()
+    }
+
+    /// Encodes the enabled params like `a=1&b=two+words`.
+    pub fn encode(params: &[Param]) -> String {
+        let mut encoded = url::form_urlencoded::Serializer::new(String::new());
+        for param in params.iter().filter(|p| p.enabled) {
+            encoded.append_pair(&param.key, &param.value);
+        }
+        encoded.finish()
+    }
+
+    /// Whether the key names a credential, like `access_token`, `client_secret` or `api_key`.
+    pub fn is_secret(&self) -> bool {
+        let key = self.key.to_ascii_lowercase();
+        let names = [
+            "token", "secret", "password", "api_key", "apikey", "api-key",
+        ];
+        names.iter().any(|name| key.contains(name))
+    }
+
+    /// The path of a multipart file part, written as `@path`.
+    pub fn file(&self) -> Option<&str> {
+        self.value.strip_prefix('@')
+    }
+}
+
+/// The most retries of a request, which with the backoff between them wait about a minute.
+pub const MAX_RETRIES: u32 = 10;
+
+/// Timeouts, retries and redirects. Unset values fall back to the app's settings, and then to the defaults.
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Settings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Retries of idempotent requests that failed to connect, timed out or got a 5xx.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+}
+
+impl Settings {
+    pub fn is_empty(&self) -> bool {
+        *self == Settings::default()
+    }
+
+    /// These settings, with the unset ones taken from `defaults`.
+    pub fn or(&self, defaults: &Settings) -> Settings {
+        Settings {
+            connect_timeout_ms: self.connect_timeout_ms.or(defaults.connect_timeout_ms),
+            timeout_ms: self.timeout_ms.or(defaults.timeout_ms),
+            retries: self.retries.or(defaults.retries),
+            follow_redirects: self.follow_redirects.or(defaults.follow_redirects),
+            max_redirects: self.max_redirects.or(defaults.max_redirects),
+        }
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_timeout_ms.unwrap_or(10_000))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(30_000))
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(0).min(MAX_RETRIES)
+    }
+
+    /// The number of redirects to follow, zero when following is turned off.
+    pub fn redirects(&self) -> usize {
+        match self.follow_redirects {
+            Some(false) => 0,
+            _ => self.max_redirects.unwrap_or(10),
+        }
+    }
+
+    /// Reads settings written like `timeout=5s connect=500ms retries=2 redirects=off`.
+    pub fn parse(line: &str) -> Result<Settings, String> {
+        let mut settings = Settings::default();
+        for pair in line.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got {pair}"))?;
+            match key {
+                "timeout" => settings.timeout_ms = Some(millis(value)?),
+                "connect" => settings.connect_timeout_ms = Some(millis(value)?),
+                "retries" => settings.retries = Some(number::<u32>(value)?.min(MAX_RETRIES)),
+                "redirects" => match value {
+                    "off" => settings.follow_redirects = Some(false),
+                    "on" => settings.follow_redirects = Some(true),
+                    max => {
+                        settings.follow_redirects = Some(true);
+                        settings.max_redirects = Some(number(max)?);
+                    }
+                },
+                _ => return Err(format!("unknown setting {key}")),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+impl Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let duration = |ms: u64| match ms % 1000 {
+            0 => format!("{}s", ms / 1000),
+            _ => format!("{ms}ms"),
+        };
+
+        let mut pairs = vec![];
+        if let Some(ms) = self.timeout_ms {
+            pairs.push(format!("timeout={}", duration(ms)));
+        }
+        if let Some(ms) = self.connect_timeout_ms {
+            pairs.push(format!("connect={}", duration(ms)));
+        }
+        if let Some(retries) = self.retries {
+            pairs.push(format!("retries={retries}"));
+        }
+        match (self.follow_redirects, self.max_redirects) {
+            (Some(false), _) => pairs.push("redirects=off".into()),
+            (_, Some(max)) => pairs.push(format!("redirects={max}")),
+            (Some(true), None) => pairs.push("redirects=on".into()),
+            (None, None) => {}
+        }
+        write!(f, "{}", pairs.join(" "))
+    }
+}
+
+/// The number of responses kept per request.
+pub const HISTORY: usize = 100;
+
+/// A response of a request, or a load run of it, kept to see how its latency changes over time,
+/// e.g. after a deploy.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Sample {
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    pub status: u16,
+    pub ms: u64,
+    /// Set for load runs, where the status is the most common one and the latency the median.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<Summary>,
+}
+
+impl Sample {
+    pub fn new(response: &Response) -> Self {
+        Self {
+            at: now(),
+            status: response.status,
+            ms: response.elapsed.as_millis() as u64,
+            load: None,
+        }
+    }
+
+    pub fn load(summary: Summary) -> Self {
+        let status = summary
+            .statuses
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(status, _)| *status)
+            .unwrap_or_default();
+
+        Self {
+            at: now(),
+            status,
+            ms: summary.p50,
+            load: Some(summary),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Milliseconds since the Unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// A random (version 4) UUID like `0b7c6f0e-3f4a-4d2e-9a51-6c1d2e3f4a5b`.
+pub fn new_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    uuid(bytes)
+}
+
+/// The bytes formatted as a UUID, in groups of 8-4-4-4-12 hex digits.
+pub fn uuid(bytes: [u8; 16]) -> String {
+    let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// An operation in an app's OpenAPI spec, e.g. `GET /api/{id}`.
+/// The hash is of the generated request, so a re-sync can tell if the spec changed. Older
+/// versions saved a hash that changed between builds as `hash`, which reads as zero.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Operation {
+    pub id: String,
+    #[serde(rename = "spec_hash", default)]
+    pub hash: u64,
+    #[serde(default)]
+    pub status: SyncStatus,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum SyncStatus {
+    #[default]
+    Synced,
+    Changed,
+    Removed,
+}
+
+/// Stores a value from a response into the app variable `var`, usable as `{{var}}` in later requests.
+#[derive(Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+pub struct Extract {
+    pub var: String,
+    pub from: Source,
+}
+
+impl Extract {
+    pub fn new(var: &str, from: Source) -> Self {
+        Self {
+            var: var.into(),
+            from,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+pub enum Source {
+    JsonPath(String),
+    Header(String),
+    Regex(String),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::JsonPath(path) => write!(f, "json {path}"),
+            Source::Header(name) => write!(f, "header {name}"),
+            Source::Regex(pattern) => write!(f, "regex {pattern}"),
+        }
+    }
+}
+
+/// Expectation on the response of a request, checked by the runner.
+#[derive(Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+pub enum Assert {
+    Status(u16),
+    JsonEquals(String, String),
+    JsonMatches(String, String),
+    Header(String),
+    TimeUnder(u64),
+}
+
+impl Display for Assert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assert::Status(status) => write!(f, "status {status}"),
+            Assert::JsonEquals(path, value) => write!(f, "json {path} == {value}"),
+            Assert::JsonMatches(path, pattern) => write!(f, "json {path} ~ {pattern}"),
+            Assert::Header(name) => write!(f, "header {name}"),
+            Assert::TimeUnder(ms) => write!(f, "time {ms}"),
+        }
+    }
+}
+
+/// A folder or a request in the tree of an app's requests.
+#[derive(Clone, Debug)]
+pub enum Node {
+    Folder(String, Vec<Node>),
+    Request(Box<Request>),
+}
+
+impl Node {
+    fn insert(nodes: &mut Vec<Node>, folders: &[&str], request: Request) {
+        let Some((name, rest)) = folders.split_first() else {
+            nodes.push(Node::Request(Box::new(request)));
+            return;
+        };
+
+        let position = nodes
+            .iter()
+            .position(|n| matches!(n, Node::Folder(folder, _) if folder == name));
+
+        let idx = position.unwrap_or_else(|| {
+            nodes.push(Node::Folder(name.to_string(), vec![]));
+            nodes.len() - 1
+        });
+
+        if let Node::Folder(_, children) = &mut nodes[idx] {
+            Node::insert(children, rest, request);
+        }
+    }
+
+    pub fn flatten(nodes: Vec<Node>) -> Vec<Request> {
+        nodes
+            .into_iter()
+            .flat_map(|node| match node {
+                Node::Folder(_, children) => Node::flatten(children),
+                Node::Request(request) => vec![*request],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    fn app(requests: &[(&str, &str)]) -> App {
+        let mut app = App::named("utsjekk");
+        app.requests = requests
+            .iter()
+            .map(|(id, folder)| Request {
+                id: id.to_string(),
+                folder: folder.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        app
+    }
+
+    fn ids(app: &App) -> Vec<String> {
+        app.ordered().iter().map(|r| r.id.clone()).collect()
+    }
+
+    #[test]
+    fn tree_groups_folders_in_order_of_appearance() {
+        let app = app(&[("1", "b"), ("2", ""), ("3", "a/x"), ("4", "b"), ("5", "a")]);
+
+        assert_eq!(ids(&app), vec!["1", "4", "2", "3", "5"]);
+    }
+
+    #[test]
+    fn move_request_within_folder() {
+        let mut app = app(&[("1", "a"), ("2", "a"), ("3", "")]);
+
+        app.move_request("2", -1);
+        assert_eq!(ids(&app), vec!["2", "1", "3"]);
+
+        app.move_request("2", -1);
+        assert_eq!(ids(&app), vec!["2", "1", "3"]);
+    }
+
+    #[test]
+    fn duplicate_and_remove_request() {
+        let mut app = app(&[("1", "a"), ("2", "a")]);
+        app.requests[0].history = vec![Sample {
+            at: 0,
+            status: 200,
+            ms: 12,
+            load: None,
+        }];
+
+        let copy = app.duplicate_request("1").unwrap();
+        assert_ne!(copy.id, "1");
+        assert!(copy.history.is_empty());
+        assert_eq!(ids(&app), vec!["1", copy.id.as_str(), "2"]);
+
+        app.remove_request("1");
+        assert_eq!(ids(&app), vec![copy.id.as_str(), "2"]);
+        assert!(app.remove_request("1").is_none());
+    }
+
+    #[test]
+    fn new_ids_are_v4_uuids() {
+        let (a, b) = (new_id(), new_id());
+
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 36);
+        assert_eq!(&a[14..15], "4");
+        assert!(matches!(&a[19..20], "8" | "9" | "a" | "b"));
+    }
+
+    #[test]
+    fn move_nested_folder() {
+        let mut app = app(&[("1", "a/x"), ("2", "a/y"), ("3", "a/y"), ("4", "x")]);
+
+        app.move_folder(&["a".into(), "y".into()], -1);
+        assert_eq!(ids(&app), vec!["2", "3", "1", "4"]);
+
+        app.move_folder(&["a".into()], 1);
+        assert_eq!(ids(&app), vec!["4", "2", "3", "1"]);
+    }
+
+    #[test]
+    fn path_with_encoded_query() {
+        let mut req = Request::new(Method::Get, "/api/sak?a=1", vec![], "");
+        req.query = vec![
+            Param::new("navn", "Ola Nordmann"),
+            Param {
+                enabled: false,
+                ..Param::new("debug", "true")
+            },
+            Param::new("fom", "2024-01-01&x"),
+        ];
+
+        assert_eq!(
+            req.path_with_query(),
+            "/api/sak?a=1&navn=Ola+Nordmann&fom=2024-01-01%26x"
+        );
+
+        req.split_query();
+        assert_eq!(req.path, "/api/sak");
+        assert_eq!(req.query.last(), Some(&Param::new("a", "1")));
+    }
+
+    #[test]
+    fn picked_host_falls_back_to_first() {
+        let mut app = App::named("app");
+        assert_eq!(app.host(), None);
+
+        app.hosts = vec!["a.intern".into(), "a.ekstern".into()];
+        assert_eq!(app.host(), Some("a.intern"));
+
+        app.host = Some("a.ekstern".into());
+        assert_eq!(app.host(), Some("a.ekstern"));
+
+        app.hosts.pop();
+        assert_eq!(app.host(), Some("a.intern"));
+    }
+
+    #[test]
+    fn settings_fall_back_to_app() {
+        let app = Settings::parse("timeout=5s retries=2 redirects=3").unwrap();
+        let req = Settings::parse("timeout=1500ms redirects=off").unwrap();
+        let settings = req.or(&app);
+
+        assert_eq!(settings.timeout(), Duration::from_millis(1500));
+        assert_eq!(settings.connect_timeout(), Duration::from_secs(10));
+        assert_eq!(settings.retries(), 2);
+        assert_eq!(settings.redirects(), 0);
+        assert_eq!(req.to_string(), "timeout=1500ms redirects=off");
+        assert!(Settings::parse("timeout=soon").is_err());
+        let many = Settings::parse("retries=1000").unwrap();
+        assert_eq!(many.retries, Some(MAX_RETRIES));
+    }
+
+    #[test]
+    fn record_keeps_latest_history() {
+        let mut app = app(&[("1", "")]);
+        for ms in 0..HISTORY as u64 + 5 {
+            let res = Response {
+                status: 200,
+                headers: vec![],
+                body: "".into(),
+                bytes: vec![],
+                elapsed: Duration::from_millis(ms),
+                timing: Default::default(),
+                sizes: Default::default(),
+            };
+            app.record("1", Sample::new(&res));
+        }
+
+        let history = &app.requests[0].history;
+        assert_eq!(history.len(), HISTORY);
+        assert_eq!(history.first().map(|s| s.ms), Some(5));
+        assert_eq!(history.last().map(|s| s.ms), Some(HISTORY as u64 + 4));
+    }
+
+    #[test]
+    fn save_replaces_state_and_keeps_backups() {
+        let dir = TempDir::new("state");
+        let mut state = State {
+            path: dir.join("state.json"),
+            backups: 2,
+            ..State::default()
+        };
+
+        for name in ["a", "b", "c", "d"] {
+            let mut app = app(&[]);
+            app.name = name.into();
+            state.insert(app);
+            state.save();
+        }
+
+        let apps = |path: &str| {
+            let json = fs::read_to_string(dir.join(path)).unwrap();
+            serde_json::from_str::<State>(&json).unwrap().keys().len()
+        };
+        assert_eq!(apps("state.json"), 4);
+        assert_eq!(apps("state.json.1"), 3);
+        assert_eq!(apps("state.json.2"), 2);
+        assert!(!dir.join("state.json.3").exists());
+        assert!(!dir.join("state.json.tmp").exists());
+
+        let mode = fs::metadata(dir.join("state.json"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn shared_requests_that_fail_to_save_are_reported() {
+        let dir = TempDir::new("problems");
+        // a file where the workspace should be, so its folders can't be created
+        fs::write(dir.join("workspace"), "").unwrap();
+        let mut state = State {
+            path: dir.join("state.json"),
+            workspace: Some(dir.join("workspace")),
+            ..State::default()
+        };
+        let mut app = app(&[("1", "")]);
+        app.requests[0].shared = true;
+        state.insert(app);
+
+        state.save();
+
+        assert!(dir.join("state.json").exists());
+        assert_eq!(state.problems().len(), 1);
+        assert!(state.problems().is_empty());
+    }
+
+    #[test]
+    fn instances_merge_each_others_saves() {
+        let dir = TempDir::new("instances");
+        let path = dir.join("state.json");
+        let mut first = State {
+            path: path.clone(),
+            ..State::default()
+        };
+        first.insert(app(&[("1", "")]));
+        first.save();
+        let mut second = first.clone();
+
+        first.get_mut("utsjekk").unwrap().requests[0].folder = "a".into();
+        first.get_mut("utsjekk").unwrap().requests[0].modified = 1;
+        first.save();
+        second.get_mut("utsjekk").unwrap().requests.push(Request {
+            id: "2".into(),
+            ..Default::default()
+        });
+        second.save();
+        assert!(first.sync().is_some());
+        assert_eq!(ids(first.get("utsjekk").unwrap()), vec!["1", "2"]);
+        assert!(first.sync().is_none());
+
+        let json = fs::read_to_string(&path).unwrap();
+        let saved = serde_json::from_str::<State>(&json).unwrap();
+        assert_eq!(ids(saved.get("utsjekk").unwrap()), vec!["1", "2"]);
+        assert_eq!(saved.get("utsjekk").unwrap().requests[0].folder, "a");
+    }
+
+    #[test]
+    fn secrets_are_scrubbed_and_never_saved() {
+        let mut app = app(&[]);
+        app.azure.client_id = "id".into();
+        app.azure.client_secret = "hemmelig".into();
+        let mut state = State::default();
+        state.insert(app);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(!json.contains("hemmelig"));
+        assert!(json.contains("\"client_id\":\"id\""));
+        assert_eq!(scrub(&json), None);
+
+        let old = json.replace("\"client_id\":\"id\"", "\"client_id\":\"id\",\"client_secret\":\"hemmelig\"");
+        let scrubbed = scrub(&old).unwrap();
+        assert!(!scrubbed.contains("hemmelig"));
+        assert!(serde_json::from_str::<State>(&scrubbed).is_ok());
+    }
+
+    #[test]
+    fn scrubbed_files_are_replaced_and_private() {
+        let dir = TempDir::new("scrub");
+        let path = dir.join("state.json");
+        fs::write(&path, r#"{"apps":{"a":{"azure":{"client_secret":"hemmelig"}}}}"#).unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o644)).unwrap();
+
+        let mut state = State::default();
+        state.scrub_secrets(&path);
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("hemmelig"));
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(state.problems().is_empty());
+    }
+
+    #[test]
+    fn parse_upgrades_and_keeps_unknown_fields() {
+        let dir = TempDir::new("parse");
+        let source = dir.join("state.json");
+        let json = r#"{"apps":{"utsjekk":{"name":"utsjekk","pod":"","cluster":"","ns":"helved",
+            "hosts":[],"azure":{"name":"utsjekk","ns":"helved","client_id":"","client_secret":"s",
+            "token_endpoint":""},"requests":[{"id":1,"method":"Get","path":"/","desc":"",
+            "headers":[],"body":"","timeout":"1s"}],"color":"red"}},"theme":"dark"}"#;
+        fs::write(&source, json).unwrap();
+
+        let state = State::parse(json, &source).unwrap();
+        let saved = serde_json::to_value(&state).unwrap();
+
+        assert_eq!(state.version, migrate::VERSION);
+        assert_eq!(saved["theme"], "dark");
+        assert_eq!(saved["apps"]["utsjekk"]["color"], "red");
+        assert_eq!(saved["apps"]["utsjekk"]["requests"][0]["timeout"], "1s");
+        let backup = dir.join("state.json.v0");
+        let kept = fs::read_to_string(&backup).unwrap();
+        assert!(!kept.contains("client_secret"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&kept).unwrap()["theme"], "dark");
+        assert_eq!(fs::metadata(&backup).unwrap().permissions().mode() & 0o777, 0o600);
     }
 }
@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use ratatui::style::{
+    palette::tailwind::{self, Palette},
+    Color,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// The colors of every view, read when they are drawn so switching theme shows at once.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Theme {
+    pub buffer_bg: Color,
+    pub header_bg: Color,
+    pub header_fg: Color,
+    pub row_fg: Color,
+    pub selected_style_fg: Color,
+    pub normal_row: Color,
+    pub alt_row: Color,
+    pub footer_boarder: Color,
+    pub header_boarder: Color,
+    /// Secondary text, like timings.
+    pub muted: Color,
+    pub error: Color,
+    pub warning: Color,
+    pub success: Color,
+    pub info: Color,
+    /// The matched chars of a search.
+    pub matched: Color,
+    /// The borders of the fields in the request editor, in turn.
+    pub fields: [Color; 7],
+}
+
+impl Theme {
+    pub const fn dark(color: &Palette) -> Self {
+        Self {
+            buffer_bg: tailwind::SLATE.c950,
+            header_bg: color.c900,
+            header_fg: tailwind::SLATE.c200,
+            row_fg: tailwind::SLATE.c200,
+            selected_style_fg: color.c400,
+            normal_row: tailwind::SLATE.c950,
+            alt_row: tailwind::SLATE.c900,
+            footer_boarder: color.c400,
+            header_boarder: color.c400,
+            muted: tailwind::SLATE.c400,
+            error: tailwind::RED.c400,
+            warning: tailwind::YELLOW.c400,
+            success: tailwind::GREEN.c400,
+            info: tailwind::SKY.c400,
+            matched: tailwind::AMBER.c400,
+            fields: [
+                tailwind::RED.c700,
+                tailwind::YELLOW.c700,
+                tailwind::GREEN.c700,
+                tailwind::BLUE.c700,
+                tailwind::INDIGO.c700,
+                tailwind::PURPLE.c700,
+                tailwind::PINK.c700,
+            ],
+        }
+    }
+
+    pub const fn light(color: &Palette) -> Self {
+        Self {
+            buffer_bg: tailwind::SLATE.c50,
+            header_bg: color.c200,
+            header_fg: tailwind::SLATE.c900,
+            row_fg: tailwind::SLATE.c900,
+            selected_style_fg: color.c700,
+            normal_row: tailwind::SLATE.c50,
+            alt_row: tailwind::SLATE.c200,
+            footer_boarder: color.c700,
+            header_boarder: color.c700,
+            muted: tailwind::SLATE.c600,
+            error: tailwind::RED.c700,
+            warning: tailwind::AMBER.c700,
+            success: tailwind::GREEN.c700,
+            info: tailwind::SKY.c700,
+            matched: tailwind::ORANGE.c600,
+            fields: [
+                tailwind::RED.c500,
+                tailwind::YELLOW.c600,
+                tailwind::GREEN.c500,
+                tailwind::BLUE.c500,
+                tailwind::INDIGO.c500,
+                tailwind::PURPLE.c500,
+                tailwind::PINK.c500,
+            ],
+        }
+    }
+
+    /// Black and white with bright colors, using the terminal's own 16 colors.
+    pub const HIGH_CONTRAST: Theme = Theme {
+        buffer_bg: Color::Black,
+        header_bg: Color::White,
+        header_fg: Color::Black,
+        row_fg: Color::White,
+        selected_style_fg: Color::Yellow,
+        normal_row: Color::Black,
+        alt_row: Color::Black,
+        footer_boarder: Color::White,
+        header_boarder: Color::White,
+        muted: Color::White,
+        error: Color::LightRed,
+        warning: Color::LightYellow,
+        success: Color::LightGreen,
+        info: Color::LightCyan,
+        matched: Color::LightYellow,
+        fields: [Color::White; 7],
+    };
+
+    /// The terminal's own colors, selected rows are still shown reversed.
+    pub const NO_COLOR: Theme = Theme {
+        buffer_bg: Color::Reset,
+        header_bg: Color::Reset,
+        header_fg: Color::Reset,
+        row_fg: Color::Reset,
+        selected_style_fg: Color::Reset,
+        normal_row: Color::Reset,
+        alt_row: Color::Reset,
+        footer_boarder: Color::Reset,
+        header_boarder: Color::Reset,
+        muted: Color::Reset,
+        error: Color::Reset,
+        warning: Color::Reset,
+        success: Color::Reset,
+        info: Color::Reset,
+        matched: Color::Reset,
+        fields: [Color::Reset; 7],
+    };
+}
+
+const PALETTES: [(&str, Palette); 4] = [
+    ("blue", tailwind::BLUE),
+    ("emerald", tailwind::EMERALD),
+    ("indigo", tailwind::INDIGO),
+    ("red", tailwind::RED),
+];
+
+pub const DEFAULT: &str = "emerald";
+
+/// A theme in the config, the colors that differ from its base, like
+/// `{ "base": "blue-light", "header_bg": "#dbeafe", "error": "magenta" }`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, String>,
+}
+
+struct Themes {
+    all: Vec<(String, Theme)>,
+    current: usize,
+}
+
+impl Themes {
+    fn builtin() -> Self {
+        let mut all = vec![];
+        for (name, palette) in &PALETTES {
+            all.push((name.to_string(), Theme::dark(palette)));
+            all.push((format!("{name}-light"), Theme::light(palette)));
+        }
+        all.push(("high-contrast".into(), Theme::HIGH_CONTRAST));
+        all.push(("no-color".into(), Theme::NO_COLOR));
+        let current = all.iter().position(|(n, _)| n == DEFAULT).unwrap_or(0);
+        Self { all, current }
+    }
+
+    fn get(&self, name: &str) -> Option<Theme> {
+        self.all.iter().find(|(n, _)| n == name).map(|(_, t)| *t)
+    }
+
+    fn add(&mut self, name: &str, config: &ThemeConfig) -> Result<()> {
+        let base = config.base.as_deref().unwrap_or(DEFAULT);
+        let mut theme = self
+            .get(base)
+            .ok_or_else(|| eyre!("theme {name} is based on unknown theme {base}"))?;
+
+        for (key, value) in &config.colors {
+            let color = Color::from_str(value)
+                .map_err(|_| eyre!("theme {name} has invalid color {key}: {value}"))?;
+            let field = match key.as_str() {
+                "buffer_bg" => &mut theme.buffer_bg,
+                "header_bg" => &mut theme.header_bg,
+                "header_fg" => &mut theme.header_fg,
+                "row_fg" => &mut theme.row_fg,
+                "selected" => &mut theme.selected_style_fg,
+                "normal_row" => &mut theme.normal_row,
+                "alt_row" => &mut theme.alt_row,
+                "footer_border" => &mut theme.footer_boarder,
+                "header_border" => &mut theme.header_boarder,
+                "muted" => &mut theme.muted,
+                "error" => &mut theme.error,
+                "warning" => &mut theme.warning,
+                "success" => &mut theme.success,
+                "info" => &mut theme.info,
+                "matched" => &mut theme.matched,
+                "fields" => {
+                    theme.fields = [color; 7];
+                    continue;
+                }
+                _ => return Err(eyre!("theme {name} has unknown color {key}")),
+            };
+            *field = color;
+        }
+
+        match self.all.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = theme,
+            None => self.all.push((name.to_string(), theme)),
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, name: &str) -> bool {
+        match self.all.iter().position(|(n, _)| n == name) {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+static THEMES: LazyLock<RwLock<Themes>> = LazyLock::new(|| RwLock::new(Themes::builtin()));
+
+/// Adds the themes of the config and selects its theme, or `no-color` when `NO_COLOR` is set.
+pub fn init(config: &Config) -> Result<()> {
+    let mut themes = THEMES.write().expect("themes lock");
+    for (name, theme) in &config.themes {
+        themes.add(name, theme)?;
+    }
+
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let name = match no_color {
+        true => "no-color",
+        false => config.theme.as_deref().unwrap_or(DEFAULT),
+    };
+    if !themes.select(name) {
+        return Err(eyre!("unknown theme {name}"));
+    }
+    Ok(())
+}
+
+pub fn current() -> Theme {
+    let themes = THEMES.read().expect("themes lock");
+    themes.all[themes.current].1
+}
+
+pub fn name() -> String {
+    let themes = THEMES.read().expect("themes lock");
+    themes.all[themes.current].0.clone()
+}
+
+pub fn names() -> Vec<String> {
+    let themes = THEMES.read().expect("themes lock");
+    themes.all.iter().map(|(n, _)| n.clone()).collect()
+}
+
+/// Switches to the theme, returns whether there is one by that name.
+pub fn select(name: &str) -> bool {
+    THEMES.write().expect("themes lock").select(name)
+}
+
+/// Switches to the next theme and returns its name.
+pub fn next() -> String {
+    let mut themes = THEMES.write().expect("themes lock");
+    themes.current = (themes.current + 1) % themes.all.len();
+    themes.all[themes.current].0.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_themes_override_their_base() {
+        let mut themes = Themes::builtin();
+        let config = serde_json::from_str::<ThemeConfig>(
+            r##"{ "base": "blue-light", "error": "magenta", "header_bg": "#102030" }"##,
+        )
+        .unwrap();
+
+        themes.add("mine", &config).unwrap();
+        let mine = themes.get("mine").unwrap();
+        assert_eq!(mine.error, Color::Magenta);
+        assert_eq!(mine.header_bg, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(mine.row_fg, Theme::light(&tailwind::BLUE).row_fg);
+        assert!(themes.select("mine"));
+    }
+
+    #[test]
+    fn invalid_user_themes_are_errors() {
+        let mut themes = Themes::builtin();
+        let theme = |json| serde_json::from_str::<ThemeConfig>(json).unwrap();
+
+        assert!(themes.add("a", &theme(r#"{ "base": "nope" }"#)).is_err());
+        assert!(themes
+            .add("b", &theme(r#"{ "error": "not a color" }"#))
+            .is_err());
+        assert!(themes.add("c", &theme(r#"{ "borders": "red" }"#)).is_err());
+    }
+}
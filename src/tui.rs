@@ -1,54 +1,104 @@
-use std::io::{self, stdout};
+use std::io::{self, stdout, Write};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use color_eyre::eyre::{eyre, Result};
+use futures::executor::block_on;
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
+        event::{DisableBracketedPaste, EnableBracketedPaste},
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
         ExecutableCommand,
     },
-    style::palette::tailwind::{Palette, BLUE, EMERALD, INDIGO, RED},
     Frame, Terminal,
 };
 
 use crate::{
-    state::{App, State},
-    views::{apps::AppsTableView, hosts::IngressView, requests::RequestView},
+    k8s,
+    keymap::Context,
+    load::Plan,
+    postman,
+    state::{App, Request, State},
+    views::{
+        apps::AppsTableView,
+        command::{self, CommandLine},
+        help,
+        hosts::IngressView,
+        load::LoadView,
+        logs::Logs,
+        requests::{RequestMode, RequestView},
+        runner::RunnerView,
+        search::{Finder, Target},
+    },
 };
 
 pub fn init() -> io::Result<Terminal<impl Backend>> {
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableBracketedPaste)?;
     enable_raw_mode()?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 pub fn restore() -> io::Result<()> {
+    stdout().execute(DisableBracketedPaste)?;
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
 
-const THEMES: [Palette; 4] = [BLUE, EMERALD, INDIGO, RED];
-pub const THEME: &Palette = &THEMES[1];
+/// Copies text to the system clipboard with an OSC 52 escape sequence,
+/// which also works from within ssh and tmux (with `set-clipboard on`).
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut out = stdout();
+    write!(out, "\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text))?;
+    out.flush()
+}
 
 pub fn layout(tui: &mut TUI, frame: &mut Frame) {
     match &mut tui.view {
         View::Apps(view) => view.render(frame),
         View::Ingresses(view) => view.render(frame),
         View::Requests(view) => view.render(frame),
+        View::Runner(view) => view.render(frame),
+        View::Load(view) => view.render(frame),
     };
+    if let Some(finder) = &mut tui.finder {
+        finder.render(frame);
+    }
+    if let Some(logs) = &mut tui.logs {
+        logs.render(frame);
+    }
+    if let Some(line) = &tui.command {
+        line.render(frame);
+    } else if let Some(status) = &tui.status {
+        command::render_status(frame, status);
+    }
+    if tui.help {
+        help::render(frame, tui.context());
+    }
 }
 
-#[derive(Clone)]
 pub enum View {
     Apps(AppsTableView),
     Ingresses(IngressView),
-    Requests(RequestView),
+    Requests(Box<RequestView>),
+    Runner(RunnerView),
+    Load(Box<LoadView>),
 }
 
-#[derive(Clone)]
 pub struct TUI {
     pub view: View,
     pub state: State,
+    /// The ctrl-p finder, shown over the view while open.
+    pub finder: Option<Finder>,
+    /// The keys of the view, shown over it until any key is pressed.
+    pub help: bool,
+    /// The `:` command line, shown over the footer while open.
+    pub command: Option<CommandLine>,
+    /// What the last command did, shown over the footer until the next key.
+    pub status: Option<String>,
+    pub logs: Option<Logs>,
 }
 
 impl Drop for TUI {
@@ -62,6 +112,27 @@ impl TUI {
         Self {
             view: View::Apps(AppsTableView::new(&state)),
             state,
+            finder: None,
+            help: false,
+            command: None,
+            status: None,
+            logs: None,
+        }
+    }
+
+    /// Where keys are pressed, which picks the keymap to use.
+    pub fn context(&self) -> Context {
+        match &self.view {
+            View::Apps(_) => Context::Apps,
+            View::Ingresses(_) => Context::Hosts,
+            View::Requests(view) => match view.mode {
+                RequestMode::Normal => Context::Requests,
+                RequestMode::Insert(_, _) | RequestMode::Import(_) => Context::Editor,
+                RequestMode::Save(_, _) | RequestMode::Load(_) => Context::Prompt,
+                RequestMode::Export(_, _) => Context::Export,
+            },
+            View::Runner(_) => Context::Runner,
+            View::Load(_) => Context::Load,
         }
     }
 
@@ -78,24 +149,159 @@ impl TUI {
     }
 
     pub fn select_requests(&mut self, app: &App) {
-        self.view = View::Requests(RequestView::new(app))
+        self.view = View::Requests(Box::new(RequestView::new(app)))
+    }
+
+    pub fn select_runner(&mut self, app: &App) {
+        let mut view = RunnerView::new(app);
+        view.run(&mut self.state);
+        self.view = View::Runner(view)
+    }
+
+    pub fn run_requests(&mut self) {
+        if let View::Requests(view) = &self.view {
+            let app = view.nais_app();
+            self.select_runner(&app);
+        }
+    }
+
+    pub fn select_load(&mut self, app: &App, request: Request, plan: Plan) {
+        let mut view = LoadView::new(app, request, plan);
+        view.run(&mut self.state);
+        self.view = View::Load(Box::new(view))
+    }
+
+    /// Starts the load plan entered in the requests view.
+    pub fn load_request(&mut self) {
+        if let View::Requests(view) = &mut self.view {
+            if let Some((request, plan)) = view.confirm_load() {
+                let app = view.nais_app();
+                self.select_load(&app, request, plan);
+            }
+        }
     }
 
     pub fn enter(&mut self) {
         match &mut self.view {
             View::Apps(view) => {
-                let name = view.selected_name();
+                let Some(name) = view.selected_name() else {
+                    return;
+                };
                 let app = self.get_app_by_name(&name).unwrap();
                 self.select_ingresses(&app);
             }
             View::Ingresses(view) => {
-                let app = view.nais_app();
+                let mut app = view.nais_app();
+                app.host = view.selected_host();
+                self.state.insert(app.clone());
                 self.select_requests(&app);
             }
             View::Requests(_) => {}
+            View::Runner(_) => {}
+            View::Load(_) => {}
+        }
+    }
+
+    pub fn find(&mut self) {
+        self.finder = Some(Finder::new(&self.state));
+    }
+
+    /// Opens the app or request selected in the finder.
+    pub fn jump(&mut self) {
+        let Some(target) = self.finder.take().and_then(|f| f.selected()) else {
+            return;
+        };
+        if let View::Load(view) = &mut self.view {
+            view.cancel(&mut self.state);
+        }
+
+        match target {
+            Target::App(name) => {
+                if let Some(app) = self.get_app_by_name(&name) {
+                    self.select_ingresses(&app);
+                }
+            }
+            Target::Request { app, id } => {
+                if let Some(app) = self.get_app_by_name(&app) {
+                    let mut view = RequestView::new(&app);
+                    view.select_id(&id);
+                    self.view = View::Requests(Box::new(view));
+                }
+            }
         }
     }
 
+    pub fn open_command(&mut self) {
+        self.command = Some(CommandLine::new(self.context()));
+    }
+
+    /// The app of the view, the selected one in the apps view.
+    pub fn app(&self) -> Option<App> {
+        let name = match &self.view {
+            View::Apps(view) => view.selected_name()?,
+            View::Ingresses(view) => view.nais_app().name,
+            View::Requests(view) => view.nais_app().name,
+            View::Runner(view) => view.nais_app().name,
+            View::Load(view) => view.nais_app().name,
+        };
+        self.get_app_by_name(&name)
+    }
+
+    /// Filters the view like a search typed after `/`.
+    pub fn filter(&mut self, query: &str) -> Result<()> {
+        let search = match &mut self.view {
+            View::Apps(view) => &mut view.search,
+            View::Ingresses(view) => &mut view.search,
+            View::Requests(view) => &mut view.search,
+            View::Runner(_) | View::Load(_) => return Err(eyre!("nothing to filter here")),
+        };
+        search.clear();
+        search.query = query.into();
+        match &mut self.view {
+            View::Apps(view) => view.searched(),
+            View::Ingresses(view) => view.searched(),
+            View::Requests(view) => view.searched(),
+            View::Runner(_) | View::Load(_) => {}
+        }
+        Ok(())
+    }
+
+    pub fn import_curl(&mut self, command: &str) -> Result<()> {
+        let View::Requests(view) = &mut self.view else {
+            return Err(eyre!("open the requests of an app to import into"));
+        };
+        view.import_curl(command, &mut self.state);
+        Ok(())
+    }
+
+    /// Writes the app's requests as a Postman collection, returns the file written.
+    pub fn export_postman(&self, file: Option<String>) -> Result<String> {
+        let app = self.app().ok_or_else(|| eyre!("no app selected"))?;
+        let file = file.unwrap_or_else(|| format!("{}.postman_collection.json", app.name));
+        std::fs::write(&file, postman::export(&app)?)?;
+        Ok(file)
+    }
+
+    pub fn show_logs(&mut self) -> Result<()> {
+        let app = self.app().ok_or_else(|| eyre!("no app selected"))?;
+        if app.pod.is_empty() {
+            return Err(eyre!("{} has no pod, refresh the apps", app.name));
+        }
+        let text = block_on(k8s::logs(&app.pod, &app.ns))?;
+        self.logs = Some(Logs::new(&app.pod, &text));
+        Ok(())
+    }
+
+    /// Deletes the pod of the app, returns its name.
+    pub fn restart(&mut self) -> Result<String> {
+        let app = self.app().ok_or_else(|| eyre!("no app selected"))?;
+        if app.pod.is_empty() {
+            return Err(eyre!("{} has no pod, refresh the apps", app.name));
+        }
+        block_on(k8s::restart(&app.pod, &app.ns))?;
+        Ok(app.pod)
+    }
+
     pub fn add_random_request(&mut self) {
         if let View::Requests(view) = &mut self.view {
             view.add_random_request(&mut self.state);
@@ -110,6 +316,35 @@ impl TUI {
                 let app = view.nais_app();
                 self.select_ingresses(&app);
             }
+            View::Runner(view) => {
+                let app = view.nais_app();
+                self.select_requests(&app);
+            }
+            View::Load(view) => {
+                view.cancel(&mut self.state);
+                let app = view.nais_app();
+                self.select_requests(&app);
+            }
+        }
+    }
+
+    /// Shows what went wrong saving, and the changes other instances saved once they are merged
+    /// into the state.
+    pub fn sync(&mut self) {
+        let problems = self.state.problems();
+        if !problems.is_empty() {
+            self.status = Some(problems.join(", "));
+        }
+        let Some(seen) = self.state.sync() else {
+            return;
+        };
+
+        match &mut self.view {
+            View::Apps(view) => view.sync(&self.state),
+            View::Ingresses(view) => view.sync(&self.state),
+            View::Requests(view) => view.sync(&self.state, &seen),
+            View::Runner(view) => view.sync(&self.state),
+            View::Load(view) => view.sync(&self.state),
         }
     }
 
@@ -117,6 +352,8 @@ impl TUI {
         match &mut self.view {
             View::Apps(view) => view.update(&mut self.state),
             View::Ingresses(view) => view.update(&mut self.state),
+            View::Runner(view) => view.run(&mut self.state),
+            View::Load(view) => view.run(&mut self.state),
             _ => {}
         };
 
@@ -17,10 +17,111 @@ impl StringJoin for String {
     }
 }
 
+/// A byte count like `1.5 MB`.
+pub fn human_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", units[unit]),
+    }
+}
+
+/// The first `limit` bytes as `xxd`-style lines of offset, hex and printable ASCII.
+pub fn hexdump(bytes: &[u8], limit: usize) -> Vec<String> {
+    bytes[..bytes.len().min(limit)]
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+                    true => *b as char,
+                    false => '.',
+                })
+                .collect::<String>();
+            format!("{:08x}  {hex:<47}  {ascii}", i * 16)
+        })
+        .collect()
+}
+
+pub fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a number, got {value}"))
+}
+
+/// Milliseconds from `500ms`, `5s` or a plain number of milliseconds.
+pub fn millis(value: &str) -> Result<u64, String> {
+    match (value.strip_suffix("ms"), value.strip_suffix('s')) {
+        (Some(ms), _) => number(ms),
+        (None, Some(s)) => number::<u64>(s).map(|s| s * 1000),
+        (None, None) => number(value),
+    }
+}
+
+/// A directory of its own for a test, like `helved-<name>-<pid>` in the temp dir, removed with
+/// everything in it when dropped.
+#[cfg(test)]
+pub struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("helved-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        Self(dir)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn human_sizes() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    #[test]
+    fn hexdump_pdf_header() {
+        let lines = hexdump(b"%PDF-1.7\n\x00\xff", 256);
+
+        assert_eq!(
+            lines,
+            vec!["00000000  25 50 44 46 2d 31 2e 37 0a 00 ff                 %PDF-1.7..."]
+        );
+    }
+
     #[test]
     fn join_string() {
         let a = "hello".to_string();
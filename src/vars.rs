@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde_json_path::JsonPath;
+
+use crate::{
+    client::Response,
+    state::{Extract, Header, Request, Source},
+};
+
+/// Replaces every `{{name}}` in `input` with the value of the variable `name`.
+/// Unknown variables are left untouched so they are easy to spot in the response.
+pub fn substitute(input: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// A copy of the request with variables substituted in path, query, headers, body and form.
+pub fn resolve(req: &Request, vars: &BTreeMap<String, String>) -> Request {
+    let mut req = req.clone();
+    req.path = substitute(&req.path, vars);
+    req.body = substitute(&req.body, vars);
+    req.query
+        .iter_mut()
+        .chain(req.form.iter_mut())
+        .for_each(|p| p.value = substitute(&p.value, vars));
+    req.headers = req
+        .headers
+        .iter()
+        .map(|h| Header {
+            value: substitute(&h.value, vars),
+            ..h.clone()
+        })
+        .collect();
+    req
+}
+
+/// Runs the extraction rules against a response.
+/// Returns every rule's variable together with the extracted value, or `None` if it did not match.
+pub fn extract(rules: &[Extract], res: &Response) -> Vec<(String, Option<String>)> {
+    rules
+        .iter()
+        .map(|rule| (rule.var.clone(), extract_one(&rule.from, res)))
+        .collect()
+}
+
+/// The first value selected by the JSONPath in a JSON body. Strings are returned without quotes.
+pub fn query(path: &str, body: &str) -> Option<String> {
+    let path = JsonPath::parse(path).ok()?;
+    let body = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    match path.query(&body).first()? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn extract_one(source: &Source, res: &Response) -> Option<String> {
+    match source {
+        Source::JsonPath(path) => query(path, &res.body),
+        Source::Header(name) => res
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone()),
+        Source::Regex(pattern) => {
+            let re = Regex::new(pattern).ok()?;
+            let caps = re.captures(&res.body)?;
+            caps.get(1)
+                .or_else(|| caps.get(0))
+                .map(|m| m.as_str().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn response(body: &str) -> Response {
+        Response {
+            status: 201,
+            headers: vec![("Location".into(), "/iverksetting/42".into())],
+            body: body.into(),
+            bytes: body.into(),
+            elapsed: Duration::ZERO,
+            timing: Default::default(),
+            sizes: Default::default(),
+        }
+    }
+
+    #[test]
+    fn substitute_known_and_unknown() {
+        let vars = BTreeMap::from([("id".to_string(), "42".to_string())]);
+
+        assert_eq!(substitute("/status/{{id}}", &vars), "/status/42");
+        assert_eq!(substitute("/status/{{ id }}", &vars), "/status/42");
+        assert_eq!(substitute("/status/{{other}}", &vars), "/status/{{other}}");
+        assert_eq!(substitute("/status/{{id", &vars), "/status/{{id");
+    }
+
+    #[test]
+    fn extract_json_path() {
+        let rules = vec![Extract::new("id", Source::JsonPath("$.data.id".into()))];
+        let res = response(r#"{ "data": { "id": "abc-123" } }"#);
+
+        assert_eq!(
+            extract(&rules, &res),
+            vec![("id".into(), Some("abc-123".into()))]
+        );
+    }
+
+    #[test]
+    fn extract_header() {
+        let rules = vec![Extract::new("loc", Source::Header("location".into()))];
+        let res = response("");
+
+        assert_eq!(
+            extract(&rules, &res),
+            vec![("loc".into(), Some("/iverksetting/42".into()))]
+        );
+    }
+
+    #[test]
+    fn extract_regex_capture() {
+        let rules = vec![Extract::new("id", Source::Regex(r"id=(\d+)".into()))];
+        let res = response("created id=17");
+
+        assert_eq!(
+            extract(&rules, &res),
+            vec![("id".into(), Some("17".into()))]
+        );
+    }
+}
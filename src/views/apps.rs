@@ -1,6 +1,6 @@
 use ratatui::{
     layout::{Constraint, Layout, Margin, Rect},
-    style::{palette::tailwind, Color, Modifier, Style, Stylize},
+    style::{Modifier, Style, Stylize},
     text::{Line, Text},
     widgets::{
         Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
@@ -10,12 +10,14 @@ use ratatui::{
 };
 
 use crate::{
+    keymap::{self, Context},
     state::{App, State},
-    tui,
+    theme::{self, Theme},
 };
 
+use super::search::{highlight, match_style, Match, Search};
+
 const ITEM_HEIGHT: usize = 4;
-const INFO_TEXT: &str = "(q) Quit (j/k) Up/Down (r) Refresh (space) Select (-) Back";
 const TITLE: &str = "APPS";
 
 #[derive(Clone)]
@@ -24,7 +26,8 @@ pub struct AppsTableView {
     data: Vec<App>,
     max_item_lens: (u16, u16),
     scroll_state: ScrollbarState,
-    theme: TableColors,
+    theme: Theme,
+    pub search: Search,
 }
 
 impl AppsTableView {
@@ -51,14 +54,19 @@ impl AppsTableView {
             state: TableState::default().with_selected(0),
             max_item_lens: (max_name_len, max_cluster_len),
             scroll_state,
-            theme: TableColors::new(tui::THEME),
+            theme: theme::current(),
             data: state.values().into_iter().cloned().collect(), // Vec<&'a App> ?
+            search: Search::default(),
         }
     }
 
     pub fn update(&mut self, state: &mut State) {
         state.update_apps();
+        self.sync(state);
+    }
 
+    /// Shows the apps as they are in the state, without asking k8s.
+    pub fn sync(&mut self, state: &State) {
         let max_name_len = state
             .keys()
             .iter()
@@ -88,7 +96,7 @@ impl AppsTableView {
     pub fn down(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
-                match self.data.len() {
+                match self.visible().len() {
                     0 | 1 => 0,               // no scroll
                     len if i >= len - 1 => 0, // wrap-around
                     _ => i + 1,               // next scroll
@@ -103,7 +111,7 @@ impl AppsTableView {
     pub fn up(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
-                match self.data.len() {
+                match self.visible().len() {
                     0 | 1 => 0,               // no scroll
                     len if i == 0 => len - 1, // wrap-around
                     _ => i - 1,               // prev scroll
@@ -115,12 +123,32 @@ impl AppsTableView {
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
 
-    pub fn selected_name(&self) -> String {
+    pub fn selected_name(&self) -> Option<String> {
         let idx = self.state.selected().unwrap_or(0);
-        self.data[idx].name.clone()
+        let (app, _) = self.visible().into_iter().nth(idx)?;
+        Some(self.data[app].name.clone())
+    }
+
+    /// The indices of the apps matching the search, best match first.
+    fn visible(&self) -> Vec<(usize, Match)> {
+        let mut visible = self
+            .data
+            .iter()
+            .enumerate()
+            .filter_map(|(i, app)| Some((i, self.search.matches(&app.name)?)))
+            .collect::<Vec<_>>();
+        visible.sort_by_key(|(_, m)| -m.score);
+        visible
+    }
+
+    /// Selects the best match after the query changed.
+    pub fn searched(&mut self) {
+        self.state.select(Some(0));
+        self.scroll_state = self.scroll_state.position(0);
     }
 
     pub fn render(&mut self, frame: &mut Frame) {
+        self.theme = theme::current();
         let layout = Layout::vertical([
             Constraint::Length(3),
             Constraint::Min(5),
@@ -135,35 +163,6 @@ impl AppsTableView {
     }
 }
 
-#[derive(Clone)]
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_style_fg: Color,
-    normal_row: Color,
-    alt_row: Color,
-    footer_boarder: Color,
-    header_boarder: Color,
-}
-
-impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
-        Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
-            selected_style_fg: color.c400,
-            normal_row: tailwind::SLATE.c950,
-            alt_row: tailwind::SLATE.c900,
-            footer_boarder: color.c400,
-            header_boarder: color.c400,
-        }
-    }
-}
-
 impl AppsTableView {
     fn render_header(&mut self, frame: &mut Frame, layout: Rect) {
         let info_header = Paragraph::new(Line::from(TITLE))
@@ -197,16 +196,18 @@ impl AppsTableView {
             .style(header_style)
             .height(1);
 
-        let rows = self.data.iter().enumerate().map(|(i, data)| {
+        let rows = self.visible().into_iter().enumerate().map(|(i, (app, m))| {
             let color = match i % 2 {
                 0 => self.theme.normal_row,
                 _ => self.theme.alt_row,
             };
 
-            let columns = [&data.name, &data.cluster];
-            columns
+            let data = &self.data[app];
+            let name = Line::from(highlight(&data.name, &m.positions, match_style()));
+            let cluster = Line::from(data.cluster.as_str());
+            [name, cluster]
                 .into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
+                .map(|content| Cell::from(Text::from(vec![Line::default(), content])))
                 .collect::<Row>()
                 .style(Style::new().fg(self.theme.row_fg).bg(color))
                 .height(4)
@@ -249,7 +250,10 @@ impl AppsTableView {
     }
 
     fn render_footer(&mut self, frame: &mut Frame, layout: Rect) {
-        let info_footer = Paragraph::new(Line::from(INFO_TEXT))
+        let text = self.search
+            .footer()
+            .unwrap_or_else(|| keymap::current().footer(Context::Apps));
+        let info_footer = Paragraph::new(Line::from(text))
             .style(
                 Style::new()
                     .fg(self.theme.footer_boarder)
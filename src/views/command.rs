@@ -0,0 +1,150 @@
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    command::{self, COMMANDS},
+    k8s,
+    keymap::Context,
+    theme,
+};
+
+/// The `:` command line, with the completions of what is typed above it.
+pub struct CommandLine {
+    pub input: String,
+    context: Context,
+    completions: Vec<String>,
+    /// The completion put in the input by tab, which keeps the others to cycle through.
+    selected: Option<usize>,
+    /// The kube contexts to complete `env` with, read once when opened.
+    kube_contexts: Vec<String>,
+}
+
+impl CommandLine {
+    pub fn new(context: Context) -> Self {
+        let mut line = Self {
+            input: String::new(),
+            context,
+            completions: vec![],
+            selected: None,
+            kube_contexts: k8s::contexts(),
+        };
+        line.complete();
+        line
+    }
+
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    pub fn add_char(&mut self, c: char) {
+        self.input.push(c);
+        self.complete();
+    }
+
+    pub fn del_char(&mut self) {
+        self.input.pop();
+        self.complete();
+    }
+
+    /// Inserts pasted text on one line, like a curl command split over several.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\\\r\n", " ").replace("\\\n", " ");
+        self.input
+            .extend(text.chars().filter(|c| *c != '\r').map(|c| match c {
+                '\n' => ' ',
+                c => c,
+            }));
+        self.complete();
+    }
+
+    /// Puts the next completion in the input.
+    pub fn next(&mut self) {
+        if self.completions.is_empty() {
+            return;
+        }
+        let i = self
+            .selected
+            .map_or(0, |i| (i + 1) % self.completions.len());
+        self.input = self.completions[i].clone();
+        self.selected = Some(i);
+    }
+
+    fn complete(&mut self) {
+        let names = |name: &str| match name {
+            "theme" => theme::names(),
+            "env" => self.kube_contexts.clone(),
+            "import" => vec!["curl".into()],
+            "export" => vec!["postman".into()],
+            _ => vec![],
+        };
+        self.completions = command::complete(&self.input, self.context, names);
+        self.selected = None;
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = theme::current();
+        let area = frame.size();
+        let height = (self.completions.len().min(8) as u16 + 2).min(area.height.saturating_sub(3));
+        let [_, list, input] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(height),
+            Constraint::Length(3),
+        ])
+        .areas(area);
+
+        let items = self
+            .completions
+            .iter()
+            .map(|line| {
+                let usage = COMMANDS
+                    .iter()
+                    .find(|(name, _)| name == line)
+                    .map(|(_, usage)| format!(" {usage}"))
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![line.into(), usage.fg(theme.muted)]))
+            })
+            .collect::<Vec<_>>();
+        let mut state = ListState::default().with_selected(self.selected);
+        let border = Style::new().fg(theme.header_boarder);
+        let completions = List::new(items)
+            .block(Block::bordered().border_style(border))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        let line = Paragraph::new(format!(":{}", self.input)).block(
+            Block::bordered()
+                .title("(tab) complete (enter) run (esc) close")
+                .border_style(border),
+        );
+
+        if !self.completions.is_empty() {
+            frame.render_widget(Clear, list);
+            frame.render_stateful_widget(completions, list, &mut state);
+        }
+        frame.render_widget(Clear, input);
+        frame.render_widget(line, input);
+        frame.set_cursor(input.x + self.input.len() as u16 + 2, input.y + 1);
+    }
+}
+
+/// A line over the footer, for what a command did.
+pub fn render_status(frame: &mut Frame, status: &str) {
+    let theme = theme::current();
+    let area = frame.size();
+    let area = Rect {
+        y: area.height.saturating_sub(3),
+        height: area.height.min(3),
+        ..area
+    };
+    let status = Paragraph::new(status)
+        .centered()
+        .style(Style::new().fg(theme.row_fg).bg(theme.buffer_bg))
+        .block(Block::bordered().border_style(Style::new().fg(theme.footer_boarder)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(status, area);
+}
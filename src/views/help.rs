@@ -0,0 +1,38 @@
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Row, Table},
+    Frame,
+};
+
+use crate::{
+    keymap::{self, Context},
+    theme,
+};
+
+use super::centered_rect;
+
+/// The keys of the context and the global ones, from the keymap in use.
+pub fn render(frame: &mut Frame, context: Context) {
+    let keymap = keymap::current();
+    let area = centered_rect(60, 70, frame.size());
+
+    let rows = [context, Context::Global].into_iter().flat_map(|context| {
+        let heading = Row::new([Line::from(context.title()).add_modifier(Modifier::BOLD)]);
+        let actions = keymap.actions(context).into_iter().map(|(action, keys)| {
+            let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+            Row::new([keys.join(", "), action.label().to_string()])
+        });
+        std::iter::once(heading).chain(actions)
+    });
+
+    let table = Table::new(rows, [Constraint::Length(24), Constraint::Min(10)]).block(
+        Block::bordered()
+            .title("Keys (any key) close")
+            .border_style(Style::new().fg(theme::current().header_boarder)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(table, area);
+}
@@ -1,6 +1,6 @@
 use ratatui::{
     layout::{Constraint, Layout, Margin, Rect},
-    style::{palette::tailwind, Color, Modifier, Style, Stylize},
+    style::{Modifier, Style, Stylize},
     text::{Line, Text},
     widgets::{
         Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
@@ -9,48 +9,25 @@ use ratatui::{
     Frame,
 };
 
-use crate::{state::{App, State}, tui};
+use crate::{
+    keymap::{self, Context},
+    state::{App, State},
+    theme::{self, Theme},
+};
+
+use super::search::{highlight, match_style, Match, Search};
 
-const INFO_TEXT: &str = "(q) Quit (j/k) Up/Down (r) Refresh (space) Select (-) Back";
 const TITLE: &str = "HOSTS";
 const ITEM_HEIGHT: usize = 4;
 
-#[derive(Clone)]
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_style_fg: Color,
-    normal_row: Color,
-    alt_row: Color,
-    footer_boarder: Color,
-    header_boarder: Color,
-}
-
-impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
-        Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
-            selected_style_fg: color.c400,
-            normal_row: tailwind::SLATE.c950,
-            alt_row: tailwind::SLATE.c900,
-            footer_boarder: color.c400,
-            header_boarder: color.c400,
-        }
-    }
-}
-
 #[derive(Clone)]
 pub struct IngressView {
     state: TableState,
     data: App,
     max_host_len: u16,
     scroll_state: ScrollbarState,
-    theme: TableColors,
+    theme: Theme,
+    pub search: Search,
 }
 
 impl IngressView {
@@ -66,8 +43,9 @@ impl IngressView {
             state: TableState::default().with_selected(0),
             max_host_len,
             scroll_state,
-            theme: TableColors::new(tui::THEME),
+            theme: theme::current(),
             data: app.clone(),
+            search: Search::default(),
         }
     }
 
@@ -92,17 +70,30 @@ impl IngressView {
         };
     }
 
+    /// Picks up the requests other instances saved, to open them from here.
+    pub fn sync(&mut self, state: &State) {
+        if let Some(app) = state.get(&self.data.name) {
+            self.data = app.clone();
+        }
+    }
+
     pub fn nais_app(&self) -> App {
         self.data.clone()
     }
 
+    /// The host under the cursor, to send the requests of the app to.
+    pub fn selected_host(&self) -> Option<String> {
+        let i = self.state.selected()?;
+        self.visible().get(i).map(|(host, _)| host.to_string())
+    }
+
     pub fn size(&self) -> usize {
         self.data.hosts.len()
     }
 
     pub fn down(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => match self.data.hosts.len() {
+            Some(i) => match self.visible().len() {
                 0 | 1 => 0,
                 _ => i + 1,
             },
@@ -114,7 +105,7 @@ impl IngressView {
 
     pub fn up(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => match self.data.hosts.len() {
+            Some(i) => match self.visible().len() {
                 0 | 1 => 0,
                 len if i == 0 => len - 1,
                 _ => i - 1,
@@ -125,7 +116,26 @@ impl IngressView {
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
 
+    /// The hosts matching the search, best match first.
+    fn visible(&self) -> Vec<(&str, Match)> {
+        let mut visible = self
+            .data
+            .hosts
+            .iter()
+            .filter_map(|host| Some((host.as_str(), self.search.matches(host)?)))
+            .collect::<Vec<_>>();
+        visible.sort_by_key(|(_, m)| -m.score);
+        visible
+    }
+
+    /// Selects the best match after the query changed.
+    pub fn searched(&mut self) {
+        self.state.select(Some(0));
+        self.scroll_state = self.scroll_state.position(0);
+    }
+
     pub fn render(&mut self, frame: &mut Frame) {
+        self.theme = theme::current();
         let layout = Layout::vertical([
             Constraint::Length(3),
             Constraint::Min(5),
@@ -173,16 +183,16 @@ impl IngressView {
             .style(header_style)
             .height(1);
 
-        let rows = self.data.hosts.iter().enumerate().map(|(i, data)| {
+        let rows = self.visible().into_iter().enumerate().map(|(i, (host, m))| {
             let color = match i % 2 {
                 0 => self.theme.normal_row,
                 _ => self.theme.alt_row,
             };
 
-            let columns = vec![data];
-            columns
+            let host = Line::from(highlight(host, &m.positions, match_style()));
+            [host]
                 .into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
+                .map(|content| Cell::from(Text::from(vec![Line::default(), content])))
                 .collect::<Row>()
                 .style(Style::new().fg(self.theme.row_fg).bg(color))
                 .height(4)
@@ -219,7 +229,10 @@ impl IngressView {
     }
 
     fn render_footer(&mut self, frame: &mut Frame, layout: Rect) {
-        let info_footer = Paragraph::new(Line::from(INFO_TEXT))
+        let text = self.search
+            .footer()
+            .unwrap_or_else(|| keymap::current().footer(Context::Hosts));
+        let info_footer = Paragraph::new(Line::from(text))
             .style(
                 Style::new()
                     .fg(self.theme.footer_boarder)
@@ -0,0 +1,298 @@
+use std::sync::{Arc, Mutex};
+
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, BorderType, Chart, Dataset, GraphType, Paragraph,
+    },
+    Frame,
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+    keymap::{self, Context},
+    load::{self, Plan, Stats, Stop},
+    state::{App, Request, Sample, State},
+    theme::{self, Theme},
+};
+
+const TITLE: &str = "LOAD";
+
+pub struct LoadView {
+    data: App,
+    request: Request,
+    plan: Plan,
+    stats: Arc<Mutex<Stats>>,
+    task: Option<JoinHandle<()>>,
+    message: Option<String>,
+    theme: Theme,
+}
+
+impl LoadView {
+    pub fn new(app: &App, request: Request, plan: Plan) -> Self {
+        Self {
+            data: app.clone(),
+            request,
+            plan,
+            stats: Arc::default(),
+            task: None,
+            message: None,
+            theme: theme::current(),
+        }
+    }
+
+    pub fn run(&mut self, state: &mut State) {
+        self.cancel(state);
+        match self.data.load(&self.request, self.plan.clone()) {
+            Ok((stats, task)) => {
+                self.stats = stats;
+                self.task = Some(task);
+                self.message = None;
+            }
+            Err(err) => self.message = Some(format!("load failed: {err:#}")),
+        }
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Writes the summary to the history of the request once the run has finished.
+    pub fn poll(&mut self, state: &mut State) {
+        if self.task.as_ref().is_some_and(|t| t.is_finished()) {
+            self.task = None;
+            self.finish(state);
+        }
+    }
+
+    /// Stops a running load, the responses so far still make it into the history.
+    pub fn cancel(&mut self, state: &mut State) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            self.stats.lock().expect("load stats").done = true;
+            self.finish(state);
+        }
+    }
+
+    fn finish(&mut self, state: &mut State) {
+        let summary = self.stats.lock().expect("load stats").summary(&self.plan);
+        self.message = Some(summary.to_string());
+        self.data.record(&self.request.id, Sample::load(summary));
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    pub fn sync(&mut self, state: &State) {
+        if let Some(app) = state.get(&self.data.name) {
+            self.data = app.clone();
+            self.message = Some("merged changes saved by another instance".into());
+        }
+    }
+
+    pub fn nais_app(&self) -> App {
+        self.data.clone()
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        self.theme = theme::current();
+        let layout = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(frame.size());
+
+        let [area_latency, area_statuses] =
+            Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(layout[2]);
+
+        self.render_header(frame, layout[0]);
+        self.render_stats(frame, layout[1]);
+        self.render_latency(frame, area_latency);
+        self.render_statuses(frame, area_statuses);
+        self.render_footer(frame, layout[3]);
+    }
+}
+
+impl LoadView {
+    fn render_header(&mut self, frame: &mut Frame, layout: Rect) {
+        let title = format!(
+            "{TITLE} {} {} {} [{}]",
+            self.data.name, self.request.method, self.request.path, self.plan
+        );
+
+        let info_header = Paragraph::new(Line::from(title))
+            .style(
+                Style::new()
+                    .fg(self.theme.header_boarder)
+                    .bg(self.theme.buffer_bg),
+            )
+            .centered()
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            );
+        frame.render_widget(info_header, layout);
+    }
+
+    fn render_stats(&mut self, frame: &mut Frame, layout: Rect) {
+        let stats = self.stats.lock().expect("load stats");
+        let latencies = stats.latencies();
+        let p = |p| load::percentile(&latencies, p);
+
+        let progress = match (self.plan.stop, stats.done) {
+            (_, true) => "done".to_string(),
+            (Stop::Count(n), false) => format!("{} of {n}", stats.hits.len()),
+            (Stop::Time(t), false) => format!("{}s of {}s", stats.elapsed.as_secs(), t.as_secs()),
+        };
+
+        let mut lines = vec![
+            Line::from(format!(
+                "{} requests in {:.1}s, {:.1} rps, {progress}",
+                stats.hits.len(),
+                stats.elapsed.as_secs_f64(),
+                stats.rps()
+            )),
+            Line::from(format!(
+                "p50 {} ms  p90 {} ms  p99 {} ms",
+                p(50),
+                p(90),
+                p(99)
+            ))
+            .bold(),
+        ];
+        match (stats.errors(), &stats.last_error) {
+            (0, _) => lines.push(Line::from("0 errors")),
+            (errors, Some(last)) => lines
+                .push(Line::from(format!("{errors} errors, last: {last}")).fg(self.theme.error)),
+            (errors, None) => {
+                lines.push(Line::from(format!("{errors} errors")).fg(self.theme.error))
+            }
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .style(Style::new().fg(self.theme.row_fg).bg(self.theme.buffer_bg))
+            .block(
+                Block::bordered()
+                    .title("Stats")
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            );
+        frame.render_widget(paragraph, layout);
+    }
+
+    /// The percentiles of each second of the run.
+    fn render_latency(&mut self, frame: &mut Frame, layout: Rect) {
+        let timeline = self.stats.lock().expect("load stats").timeline();
+        let series = |i: usize| {
+            timeline
+                .iter()
+                .map(|(second, ps)| (*second as f64, ps[i] as f64))
+                .collect::<Vec<_>>()
+        };
+        let (p50, p90, p99) = (series(0), series(1), series(2));
+
+        let max_x = timeline.last().map(|(s, _)| *s).unwrap_or_default().max(1) as f64;
+        let max_y = timeline
+            .iter()
+            .map(|(_, ps)| ps[2])
+            .max()
+            .unwrap_or_default()
+            .max(1) as f64;
+
+        let dataset = |name, data, color| {
+            Dataset::default()
+                .name(name)
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(color))
+                .data(data)
+        };
+        let chart = Chart::new(vec![
+            dataset("p50", &p50, self.theme.success),
+            dataset("p90", &p90, self.theme.warning),
+            dataset("p99", &p99, self.theme.error),
+        ])
+        .block(
+            Block::bordered()
+                .title("Latency per second")
+                .border_style(Style::new().fg(self.theme.header_boarder)),
+        )
+        .style(Style::new().fg(self.theme.row_fg).bg(self.theme.buffer_bg))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, max_x])
+                .labels(vec!["0s".into(), format!("{max_x}s").into()]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max_y])
+                .labels(vec!["0".into(), format!("{max_y} ms").into()]),
+        );
+        frame.render_widget(chart, layout);
+    }
+
+    fn render_statuses(&mut self, frame: &mut Frame, layout: Rect) {
+        let stats = self.stats.lock().expect("load stats");
+        let color = |status: u16| match status {
+            200..=299 => self.theme.success,
+            300..=499 => self.theme.warning,
+            _ => self.theme.error,
+        };
+
+        let mut bars = stats
+            .statuses()
+            .into_iter()
+            .map(|(status, count)| {
+                Bar::default()
+                    .label(status.to_string().into())
+                    .value(count as u64)
+                    .style(Style::new().fg(color(status)))
+            })
+            .collect::<Vec<_>>();
+        if stats.errors() > 0 {
+            bars.push(
+                Bar::default()
+                    .label("error".into())
+                    .value(stats.errors() as u64)
+                    .style(Style::new().fg(self.theme.error)),
+            );
+        }
+
+        let chart = BarChart::default()
+            .block(
+                Block::bordered()
+                    .title("Status codes")
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            )
+            .style(Style::new().fg(self.theme.row_fg).bg(self.theme.buffer_bg))
+            .bar_width(5)
+            .bar_gap(1)
+            .data(BarGroup::default().bars(&bars));
+        frame.render_widget(chart, layout);
+    }
+
+    fn render_footer(&mut self, frame: &mut Frame, layout: Rect) {
+        let text = match &self.message {
+            Some(message) => message.clone(),
+            None => keymap::current().footer(Context::Load),
+        };
+
+        let info_footer = Paragraph::new(Line::from(text))
+            .style(
+                Style::new()
+                    .fg(self.theme.footer_boarder)
+                    .bg(self.theme.buffer_bg),
+            )
+            .centered()
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.theme.footer_boarder)),
+            );
+        frame.render_widget(info_footer, layout);
+    }
+}
@@ -0,0 +1,71 @@
+use ratatui::{
+    style::Style,
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme;
+
+use super::centered_rect;
+
+/// The last lines a pod logged, shown over the view from the end.
+pub struct Logs {
+    title: String,
+    lines: Vec<String>,
+    /// The first line shown, the end until scrolled up.
+    top: Option<usize>,
+    /// The lines that fit, as last drawn.
+    height: usize,
+}
+
+impl Logs {
+    pub fn new(pod: &str, text: &str) -> Self {
+        Self {
+            title: format!("Logs of {pod} (j/k) scroll (esc) close"),
+            lines: text.lines().map(String::from).collect(),
+            top: None,
+            height: 0,
+        }
+    }
+
+    pub fn up(&mut self) {
+        let top = self.top.unwrap_or(self.end());
+        self.top = Some(top.saturating_sub(1));
+    }
+
+    pub fn down(&mut self) {
+        let top = self.top.unwrap_or(self.end()) + 1;
+        self.top = (top < self.end()).then_some(top);
+    }
+
+    /// The top line when scrolled to the end.
+    fn end(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let theme = theme::current();
+        let area = centered_rect(90, 80, frame.size());
+        self.height = area.height.saturating_sub(2) as usize;
+        let top = self.top.unwrap_or(self.end());
+
+        let lines = self
+            .lines
+            .iter()
+            .skip(top)
+            .take(self.height)
+            .map(|l| Line::from(l.as_str()))
+            .collect::<Vec<_>>();
+        let logs = Paragraph::new(lines)
+            .style(Style::new().fg(theme.row_fg).bg(theme.buffer_bg))
+            .block(
+                Block::bordered()
+                    .title(self.title.as_str())
+                    .border_style(Style::new().fg(theme.header_boarder)),
+            );
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(logs, area);
+    }
+}
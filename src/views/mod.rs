@@ -1,5 +1,28 @@
 pub mod apps;
+pub mod command;
+pub mod help;
 pub mod hosts;
+pub mod load;
+pub mod logs;
 pub mod requests;
+pub mod runner;
+pub mod search;
 
+use ratatui::layout::{Constraint, Layout, Rect};
 
+/// A rect of the given percentages of `r`, in the middle of it.
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_lay = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_lay[1])[1]
+}
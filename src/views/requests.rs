@@ -1,97 +1,180 @@
-use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use futures::executor::block_on;
+
+use itertools::Itertools;
 use ratatui::layout::Layout;
-use ratatui::style::palette::tailwind::Palette;
 use ratatui::text::Span;
-use ratatui::widgets::{Clear, List, ListItem, ScrollbarState, TableState};
+use ratatui::widgets::{Clear, Gauge, List, ListItem, Sparkline};
 
 use ratatui::{
-    layout::{Constraint, Margin, Rect},
-    style::{palette::tailwind, Color, Modifier, Style, Stylize},
-    text::{Line, Text},
-    widgets::{
-        Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        Table,
-    },
+    layout::{Constraint, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Paragraph, Scrollbar, ScrollbarOrientation},
     Frame,
 };
+use tokio::task::JoinHandle;
+use tui_tree_widget::{Tree, TreeItem, TreeState};
 
-use crate::state::{App, Header, Method, Request, State};
+use crate::client::{Progress, Response};
+use crate::curl;
+use crate::export::{self, Format};
+use crate::keymap::{self, Action, Context};
+use crate::load::Plan;
+use crate::merge::{self, Snapshot};
+use crate::openapi::{self, Violation};
+use crate::state::{
+    App, Assert, BodyMode, Extract, Header, Method, Node, Param, Request, Sample, Settings, Source,
+    State, SyncStatus,
+};
+use crate::theme::{self, Theme};
 use crate::tui;
+use crate::util::{hexdump, human_size};
+use crate::vars;
+use crate::workspace;
 
-const INFO_TEXT: &str = "(q)uit (n)ew (e)dit (j/k) up/down (-) back ( ) select";
-const TITLE: &str = "REQUESTS";
-const ITEM_HEIGHT: usize = 4;
+use super::centered_rect;
+use super::search::{highlight, match_style, Search};
 
-#[derive(Clone)]
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_style_fg: Color,
-    normal_row: Color,
-    alt_row: Color,
-    footer_boarder: Color,
-    header_boarder: Color,
-}
-
-impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
-        Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
-            selected_style_fg: color.c400,
-            normal_row: tailwind::SLATE.c950,
-            alt_row: tailwind::SLATE.c900,
-            footer_boarder: color.c400,
-            header_boarder: color.c400,
-        }
-    }
-}
+const TITLE: &str = "REQUESTS";
 
-#[derive(Clone)]
 pub struct RequestView {
-    state: TableState,
+    state: TreeState<String>,
     pub data: App,
-    max_len: (u16, u16, u16),
-    scroll_state: ScrollbarState,
-    theme: TableColors,
+    theme: Theme,
     pub mode: RequestMode,
     pub editables: Vec<Editable>,
+    /// The key/value grid used while editing headers or query params.
+    pub grid: Grid,
+    pub response: Option<Outcome>,
+    message: Option<String>,
+    pending: Option<Pending>,
+    download: Option<(Arc<Mutex<Progress>>, JoinHandle<()>)>,
+    /// The request asked to be deleted, deleting it again confirms.
+    delete: Option<String>,
+    pub search: Search,
+}
+
+/// A request sent in the background, awaiting its response.
+struct Pending {
+    request: Request,
+    resolved: Request,
+    task: JoinHandle<color_eyre::Result<Response>>,
+}
+
+#[derive(Clone)]
+pub enum Outcome {
+    Done(Box<Response>, Vec<(String, Option<String>)>, Vec<Violation>),
+    /// The request body violates the OpenAPI schema, sending it again sends it anyway.
+    Invalid(String, Vec<Violation>),
+    Failed(String),
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum RequestMode {
     Normal,
     Insert(Editable, Field),
+    Import(Editable),
+    Export(Format, Option<String>),
+    /// Asks for the file name to save to.
+    Save(Editable, Save),
+    /// Asks for the load plan, like `n=1000 c=10`.
+    Load(Editable),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Save {
+    /// Writes the body of the last response.
+    Response,
+    /// Sends the selected request again and streams the body to the file.
+    Download,
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum Field {
     Desc,
     Path,
+    Query,
     Headers,
     Body,
+    /// The body of a form or multipart request, edited as a grid.
+    Form,
+    Extract,
+    Tags,
+    Asserts,
+    Folder,
+    Settings,
 }
 
+impl Field {
+    /// Fields edited in the key/value grid rather than as text.
+    pub fn is_grid(&self) -> bool {
+        matches!(self, Field::Headers | Field::Query | Field::Form)
+    }
+}
+
+/// Tree identifiers are prefixed so a folder and a request id can never collide.
+const FOLDER: &str = "folder:";
+const REQUEST: &str = "request:";
+
 impl RequestView {
     pub fn new(app: &App) -> Self {
-        let scroll_state = match app.requests.len() {
-            0 => ScrollbarState::default(),
-            n => ScrollbarState::new((n - 1) * ITEM_HEIGHT),
-        };
-
-        Self {
-            state: TableState::default().with_selected(0),
-            scroll_state,
-            theme: TableColors::new(tui::THEME),
+        let mut view = Self {
+            state: TreeState::default(),
+            theme: theme::current(),
             data: app.clone(),
-            max_len: (30, 30, 30),
             mode: RequestMode::Normal,
             editables: vec![],
+            grid: Grid::default(),
+            response: None,
+            message: None,
+            pending: None,
+            download: None,
+            delete: None,
+            search: Search::default(),
+        };
+
+        let tree = app.tree();
+        view.open_all(&tree, &mut vec![]);
+        match tree.first() {
+            Some(Node::Folder(name, _)) => view.state.select(vec![format!("{FOLDER}{name}")]),
+            Some(Node::Request(req)) => view.state.select(vec![format!("{REQUEST}{}", req.id)]),
+            None => false,
+        };
+        view
+    }
+
+    /// The request tree, only with the requests matching the search.
+    fn tree(&self) -> Vec<Node> {
+        if self.search.query.is_empty() {
+            return self.data.tree();
+        }
+        let mut app = self.data.clone();
+        app.requests
+            .retain(|req| self.search.matches(&search_text(req)).is_some());
+        app.tree()
+    }
+
+    /// Opens the folders with matches and selects the first one after the query changed.
+    pub fn searched(&mut self) {
+        let tree = self.tree();
+        self.open_all(&tree, &mut vec![]);
+        if let Some(req) = Node::flatten(tree).first() {
+            self.select_request(req);
+        }
+    }
+
+    fn open_all(&mut self, nodes: &[Node], path: &mut Vec<String>) {
+        for node in nodes {
+            if let Node::Folder(name, children) = node {
+                path.push(format!("{FOLDER}{name}"));
+                self.state.open(path.clone());
+                self.open_all(children, path);
+                path.pop();
+            }
         }
     }
 
@@ -106,7 +189,7 @@ impl RequestView {
             "{ body }",
         );
 
-        self.data.requests.insert(random_req);
+        self.data.add_request(random_req);
         state.insert(self.data.clone());
         state.save();
     }
@@ -116,11 +199,16 @@ impl RequestView {
             .get(&self.data.name)
             .expect("app in requestview")
             .clone();
+    }
 
-        self.scroll_state = match self.data.requests.len() {
-            0 => ScrollbarState::default(),
-            n => ScrollbarState::new((n - 1) * ITEM_HEIGHT),
-        };
+    /// Picks up what other instances saved, keeping the selection and the edits not saved yet.
+    /// `seen` is the state as it was when the view last looked, which tells those edits apart.
+    pub fn sync(&mut self, state: &State, seen: &Snapshot) {
+        if let Some(app) = state.get(&self.data.name) {
+            if merge::merge_app(&mut self.data, app, seen.get(&app.name)) {
+                self.message = Some("merged changes saved by another instance".into());
+            }
+        }
     }
 
     pub fn nais_app(&self) -> App {
@@ -132,33 +220,153 @@ impl RequestView {
     }
 
     pub fn down(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                match self.data.requests.len() {
-                    0 | 1 => 0,               // no scroll
-                    len if i >= len - 1 => 0, // wrap-around
-                    _ => i + 1,               // next scroll
-                }
-            }
-            None => 0,
-        };
-        self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+        self.delete = None;
+        self.state.key_down();
     }
 
     pub fn up(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                let up = i.saturating_sub(1);
-                up.clamp(0, self.data.requests.len() - 1)
-            }
-            None => 0,
+        self.delete = None;
+        self.state.key_up();
+    }
+
+    /// Opens or closes the selected folder.
+    pub fn toggle(&mut self) {
+        self.state.toggle_selected();
+    }
+
+    pub fn collapse(&mut self) {
+        self.state.key_left();
+    }
+
+    pub fn expand(&mut self) {
+        self.state.key_right();
+    }
+
+    /// Moves the selected request or folder among its siblings and saves the new order.
+    pub fn move_selected(&mut self, offset: isize, state: &mut State) {
+        if self.mode != RequestMode::Normal {
+            return;
+        }
+
+        match self.selected_request() {
+            Some(req) => self.data.move_request(&req.id, offset),
+            None => self.data.move_folder(&self.selected_folder(), offset),
+        }
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Shares the selected request through the repository's workspace, or stops sharing it.
+    pub fn share(&mut self, state: &mut State) {
+        let (RequestMode::Normal, Some(idx)) = (&self.mode, self.selected_index()) else {
+            return;
+        };
+
+        let req = &mut self.data.requests[idx];
+        req.shared = !req.shared;
+        let workspace = state.workspace().join(&self.data.name);
+        self.message = Some(match req.shared {
+            true => format!("sharing {} {} in {}", req.method, req.path, workspace.display()),
+            false => format!("stopped sharing {} {}", req.method, req.path),
+        });
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Adds a copy of the selected request below it and selects the copy.
+    pub fn duplicate(&mut self, state: &mut State) {
+        let (RequestMode::Normal, Some(req)) = (&self.mode, self.selected_request()) else {
+            return;
+        };
+
+        if let Some(copy) = self.data.duplicate_request(&req.id) {
+            self.message = Some(format!("copied {} {}", copy.method, copy.path));
+            self.select_request(&copy);
+        }
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Deletes the selected request once asked twice, with its shared file if it has one.
+    pub fn delete(&mut self, state: &mut State) {
+        let (RequestMode::Normal, Some(req)) = (&self.mode, self.selected_request()) else {
+            return;
         };
-        self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+
+        if self.delete.as_ref() != Some(&req.id) {
+            let again = keymap::current().hint(Context::Requests, Action::Delete);
+            self.message = Some(format!("delete {} {}? {again} again to confirm", req.method, req.path));
+            self.delete = Some(req.id);
+            return;
+        }
+
+        let ordered = self.data.ordered();
+        let idx = ordered.iter().position(|r| r.id == req.id).unwrap_or_default();
+        let neighbour = ordered.get(idx + 1).or(idx.checked_sub(1).and_then(|i| ordered.get(i)));
+        if let Some(next) = neighbour {
+            self.select_request(next);
+        }
+
+        self.delete = None;
+        self.data.remove_request(&req.id);
+        self.message = Some(format!("deleted {} {}", req.method, req.path));
+        if req.shared {
+            if let Err(err) = workspace::remove(state.workspace(), &self.data.name, &req.id) {
+                self.message = Some(format!("failed to remove the shared file: {err:#}"));
+            }
+        }
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// The selected request, or `None` if a folder (or nothing) is selected.
+    fn selected_request(&self) -> Option<Request> {
+        let id = self.state.selected().last()?.strip_prefix(REQUEST)?;
+        self.data
+            .requests
+            .iter()
+            .find(|r| r.id == id)
+            .cloned()
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let req = self.selected_request()?;
+        self.data.requests.iter().position(|r| r.id == req.id)
+    }
+
+    /// Selects the request with the id, e.g. when jumping to it from the finder.
+    pub fn select_id(&mut self, id: &str) {
+        if let Some(req) = self.data.requests.iter().find(|r| r.id == id).cloned() {
+            self.select_request(&req);
+        }
+    }
+
+    /// Selects the request in the tree, opening the folders it is in.
+    fn select_request(&mut self, req: &Request) {
+        let mut path = vec![];
+        for folder in req.folders() {
+            path.push(format!("{FOLDER}{folder}"));
+            self.state.open(path.clone());
+        }
+        path.push(format!("{REQUEST}{}", req.id));
+        self.state.select(path);
+    }
+
+    /// The folder path of the selection; the selected folder itself or the folder of the selected request.
+    fn selected_folder(&self) -> Vec<String> {
+        self.state
+            .selected()
+            .iter()
+            .filter_map(|id| id.strip_prefix(FOLDER))
+            .map(String::from)
+            .collect()
     }
 
     pub fn render(&mut self, frame: &mut Frame) {
+        self.theme = theme::current();
         let layout = Layout::vertical([
             Constraint::Length(3),
             Constraint::Min(5),
@@ -166,37 +374,375 @@ impl RequestView {
         ])
         .split(frame.size());
 
+        let [area_tree, area_response] = match self.response {
+            Some(_) => {
+                Layout::vertical([Constraint::Min(5), Constraint::Percentage(50)]).areas(layout[1])
+            }
+            None => [layout[1], Rect::default()],
+        };
+        let history = self.selected_request().map(|r| r.history).unwrap_or_default();
+        let [area_tree, area_latency] = match history.len() {
+            0 | 1 => [area_tree, Rect::default()],
+            _ => Layout::vertical([Constraint::Min(5), Constraint::Length(4)]).areas(area_tree),
+        };
+
         self.render_header(frame, layout[0]);
-        self.render_table(frame, layout[1]);
-        self.render_scrollbar(frame, layout[1]);
+        self.render_tree(frame, area_tree);
+        self.render_latency(frame, area_latency, &history);
+        self.render_response(frame, area_response);
         self.render_footer(frame, layout[2]);
         self.render_editor(frame);
+        self.render_import(frame);
+        self.render_export(frame);
+        self.render_save(frame);
+    }
+
+    pub fn send(&mut self, state: &mut State) {
+        if self.mode != RequestMode::Normal || self.pending.is_some() {
+            return;
+        }
+
+        let Some(req) = self.selected_request() else {
+            return;
+        };
+
+        let resolved = vars::resolve(&req, &self.data.vars);
+        let invalid = match &self.data.openapi {
+            Some(doc) => openapi::validate_request(doc, &resolved),
+            None => vec![],
+        };
+
+        let confirmed = matches!(&self.response, Some(Outcome::Invalid(id, _)) if *id == req.id);
+        if !invalid.is_empty() && !confirmed {
+            self.response = Some(Outcome::Invalid(req.id.clone(), invalid));
+            return;
+        }
+
+        match self.data.spawn(&req) {
+            Ok(task) => {
+                let cancel = keymap::current().hint(Context::Requests, Action::Cancel);
+                self.message = Some(format!("sending {} {} {cancel} cancel", req.method, req.path));
+                self.pending = Some(Pending {
+                    request: req,
+                    resolved,
+                    task,
+                });
+            }
+            Err(err) => self.response = Some(Outcome::Failed(format!("{err:#}"))),
+        }
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Picks up the response of the request sent in the background, once it has arrived.
+    pub fn poll(&mut self, state: &mut State) {
+        if !self.pending.as_ref().is_some_and(|p| p.task.is_finished()) {
+            return;
+        }
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        self.message = None;
+        self.response = match block_on(pending.task) {
+            Ok(Ok(res)) => {
+                let extracted = self.data.extract(&pending.request, &res);
+                self.data.record(&pending.request.id, Sample::new(&res));
+                let violations = match &self.data.openapi {
+                    Some(doc) => openapi::validate_response(doc, &pending.resolved, &res),
+                    None => vec![],
+                };
+                Some(Outcome::Done(Box::new(res), extracted, violations))
+            }
+            Ok(Err(err)) => Some(Outcome::Failed(format!("{err:#}"))),
+            Err(err) => Some(Outcome::Failed(err.to_string())),
+        };
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Cancels the request in flight and the running download, which leaves no partial file behind.
+    pub fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.task.abort();
+            self.message = Some(format!(
+                "cancelled {} {}",
+                pending.request.method, pending.request.path
+            ));
+        }
+
+        if let Some((progress, task)) = self.download.take() {
+            task.abort();
+            let path = progress.lock().expect("download progress").path.clone();
+            let _ = std::fs::remove_file(&path);
+            self.message = Some(format!("cancelled download to {}", path.display()));
+        }
     }
 
     pub fn edit(&mut self) {
-        if self.mode == RequestMode::Normal {
+        if self.mode == RequestMode::Normal && self.selected_request().is_some() {
             self.editables = self.editables();
             self.mode = RequestMode::Insert(self.editables[0].clone(), Field::Desc);
         }
     }
 
+    /// Adds an empty request to the selected folder and selects it.
     pub fn new_request(&mut self) {
         if self.mode == RequestMode::Normal {
-            let mut req = Request::default();
-            let id = self.data.requests.len() + 1;
-            req.id = id as u64;
-            self.data.requests.insert(req);
+            let folder = self.selected_folder();
+            let mut req = Request::new(Method::Get, "", vec![], "");
+            req.folder = folder.join("/");
+            self.data.add_request(req.clone());
+            self.select_request(&req);
+        }
+    }
+
+    /// Generates requests from the app's OpenAPI spec, and marks changed and removed operations.
+    pub fn discover(&mut self, state: &mut State) {
+        if self.mode != RequestMode::Normal {
+            return;
+        }
+
+        match openapi::discover(&mut self.data, None) {
+            Ok(summary) => self.message = Some(format!("OpenAPI: {summary}")),
+            Err(err) => self.response = Some(Outcome::Failed(format!("{err:#}"))),
+        }
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Opens a popup where a curl command can be typed or pasted.
+    pub fn import(&mut self) {
+        if self.mode == RequestMode::Normal {
+            self.mode = RequestMode::Import(Editable::new(vec![""]));
+        }
+    }
+
+    /// Saves the curl command in the import popup as a request in the selected folder.
+    pub fn save_import(&mut self, state: &mut State) {
+        let RequestMode::Import(editable) = &self.mode else {
+            return;
+        };
+
+        let command = editable.input.join("\n");
+        self.mode = RequestMode::Normal;
+
+        if command.trim().is_empty() {
+            return;
+        }
+
+        let mut import = match curl::parse(&command) {
+            Ok(import) => import,
+            Err(err) => {
+                self.response = Some(Outcome::Failed(format!("import failed: {err:#}")));
+                return;
+            }
+        };
+
+        if !curl::matches(&self.data, &import.host) {
+            self.response = Some(Outcome::Failed(format!(
+                "imported, but {} is not a host of {}",
+                import.host, self.data.name
+            )));
+        }
+
+        import.request.folder = self.selected_folder().join("/");
+        self.data.add_request(import.request.clone());
+        self.select_request(&import.request);
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Imports a curl command typed on the command line, like one pasted in the import popup.
+    pub fn import_curl(&mut self, command: &str, state: &mut State) {
+        self.mode = RequestMode::Import(Editable::new(vec![command]));
+        self.save_import(state);
+    }
+
+    /// Opens a popup with the selected request as a curl command.
+    pub fn export(&mut self) {
+        if self.mode == RequestMode::Normal && self.selected_request().is_some() {
+            self.message = None;
+            self.mode = RequestMode::Export(Format::default(), None);
+        }
+    }
+
+    pub fn next_format(&mut self) {
+        if let RequestMode::Export(format, _) = &mut self.mode {
+            *format = format.next();
+        }
+    }
+
+    /// Switches between a `$TOKEN` placeholder and an inlined bearer token.
+    pub fn toggle_token(&mut self) {
+        let RequestMode::Export(format, token) = &self.mode else {
+            return;
+        };
+
+        let format = *format;
+        let token = match token {
+            Some(_) => None,
+            None => match self.data.token() {
+                Ok(token) => Some(token),
+                Err(err) => {
+                    self.response = Some(Outcome::Failed(format!("{err:#}")));
+                    None
+                }
+            },
+        };
+
+        self.mode = RequestMode::Export(format, token);
+    }
+
+    pub fn copy_export(&mut self) {
+        if let Some(text) = self.exported() {
+            self.message = match tui::copy(&text) {
+                Ok(_) => Some("copied to clipboard".into()),
+                Err(err) => Some(format!("failed to copy: {err}")),
+            };
+            self.mode = RequestMode::Normal;
         }
     }
 
+    pub fn write_export(&mut self) {
+        let (Some(text), Some(req), RequestMode::Export(format, _)) =
+            (self.exported(), self.selected_request(), &self.mode)
+        else {
+            return;
+        };
+
+        let path = format!("{}-{}.{}", self.data.name, req.id, format.extension());
+        self.message = match std::fs::write(&path, text) {
+            Ok(_) => Some(format!("wrote {path}")),
+            Err(err) => Some(format!("failed to write {path}: {err}")),
+        };
+        self.mode = RequestMode::Normal;
+    }
+
+    /// Asks where to save the body of the last response.
+    pub fn save_as(&mut self) {
+        let (RequestMode::Normal, Some(Outcome::Done(res, _, _)), Some(req)) =
+            (&self.mode, &self.response, self.selected_request())
+        else {
+            return;
+        };
+
+        let extension = res.extension();
+        let name = res
+            .filename()
+            .unwrap_or(format!("{}-{}.{extension}", self.data.name, req.id));
+        self.mode = RequestMode::Save(Editable::at_end(name), Save::Response);
+    }
+
+    /// Asks where to stream the response of the selected request to.
+    pub fn download(&mut self) {
+        let (RequestMode::Normal, Some(req)) = (&self.mode, self.selected_request()) else {
+            return;
+        };
+
+        let name = match req.path.rsplit('/').next() {
+            Some(name) if !name.is_empty() && !name.contains('{') => name.to_string(),
+            _ => format!("{}-{}", self.data.name, req.id),
+        };
+        self.mode = RequestMode::Save(Editable::at_end(name), Save::Download);
+    }
+
+    pub fn confirm_save(&mut self, state: &mut State) {
+        let RequestMode::Save(editable, save) = &self.mode else {
+            return;
+        };
+        let path = editable.input.join("");
+        let save = *save;
+        self.mode = RequestMode::Normal;
+
+        match save {
+            Save::Response => {
+                let Some(Outcome::Done(res, _, _)) = &self.response else {
+                    return;
+                };
+                let size = human_size(res.bytes.len() as u64);
+                self.message = match std::fs::write(&path, &res.bytes) {
+                    Ok(_) => Some(format!("saved {size} to {path}")),
+                    Err(err) => Some(format!("failed to write {path}: {err}")),
+                };
+            }
+            Save::Download if self.download.is_some() => {
+                self.message = Some("a download is already running".into());
+            }
+            Save::Download => {
+                let Some(req) = self.selected_request() else {
+                    return;
+                };
+                match self.data.download(&req, PathBuf::from(&path)) {
+                    Ok(download) => {
+                        self.download = Some(download);
+                        self.message = None;
+                    }
+                    Err(err) => self.message = Some(format!("download failed: {err:#}")),
+                }
+                state.insert(self.data.clone());
+                state.save();
+            }
+        }
+    }
+
+    /// Asks how to load test the selected request.
+    pub fn load(&mut self) {
+        if self.mode == RequestMode::Normal && self.selected_request().is_some() {
+            let plan = Plan::default().to_string();
+            self.mode = RequestMode::Load(Editable::at_end(plan));
+        }
+    }
+
+    /// The selected request and the entered plan, to start the load with.
+    pub fn confirm_load(&mut self) -> Option<(Request, Plan)> {
+        let RequestMode::Load(editable) = &self.mode else {
+            return None;
+        };
+        let plan = Plan::parse(&editable.input.join(""));
+        self.mode = RequestMode::Normal;
+
+        match plan {
+            Ok(plan) => Some((self.selected_request()?, plan)),
+            Err(err) => {
+                self.message = Some(format!("invalid load plan: {err}"));
+                None
+            }
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.mode = RequestMode::Normal;
+    }
+
+    fn exported(&self) -> Option<String> {
+        let RequestMode::Export(format, token) = &self.mode else {
+            return None;
+        };
+
+        let req = self.selected_request()?;
+        let req = vars::resolve(&req, &self.data.vars);
+        let host = self.data.host()?;
+        Some(export::export(*format, host, &req, token.as_deref()))
+    }
+
     fn editables(&mut self) -> Vec<Editable> {
-        let idx = self.state.selected().unwrap_or(0);
-        let req = &mut self.data.requests.clone().into_iter().collect::<Vec<_>>()[idx];
+        let req = self.selected_request().unwrap_or_default();
         let e_desc = Editable::new(vec![&req.desc]);
         let e_path = Editable::new(vec![&req.path]);
-        let e_head = Editable::from(&req.headers);
         let e_body = Editable::new(vec![req.body.clone()]);
-        vec![e_desc, e_path, e_head, e_body]
+        let e_extract = Editable::from(&req.extract);
+        let e_tags = Editable::new(vec![req.tags.iter().join(" ")]);
+        let e_asserts = Editable::from(&req.asserts);
+        let e_folder = Editable::new(vec![&req.folder]);
+        let e_settings = Editable::new(vec![req.settings.to_string()]);
+        vec![
+            e_desc, e_path, e_body, e_extract, e_tags, e_asserts, e_folder, e_settings,
+        ]
     }
 
     pub fn next_edit(&mut self, state: &mut State) {
@@ -204,6 +750,7 @@ impl RequestView {
         self.save(state);
         self.mode = mode;
         self.editables = self.editables();
+        let req = self.selected_request().unwrap_or_default();
 
         if let RequestMode::Insert(_, field) = &self.mode {
             match field {
@@ -211,22 +758,71 @@ impl RequestView {
                     self.mode = RequestMode::Insert(self.editables[1].clone(), Field::Path)
                 },
                 Field::Path => {
-                    self.mode = RequestMode::Insert(self.editables[2].clone(), Field::Headers)
+                    self.grid = Grid::from(&req.query);
+                    self.mode = RequestMode::Insert(Editable::new(vec![""]), Field::Query)
                 }
-                Field::Headers => {
-                    self.mode = RequestMode::Insert(self.editables[3].clone(), Field::Body)
+                Field::Query => {
+                    self.mode = RequestMode::Insert(self.editables[6].clone(), Field::Folder)
                 }
-                Field::Body => {
+                Field::Folder => {
+                    self.mode = RequestMode::Insert(self.editables[4].clone(), Field::Tags)
+                }
+                Field::Tags => {
+                    self.mode = RequestMode::Insert(self.editables[7].clone(), Field::Settings)
+                }
+                Field::Settings => {
+                    self.grid = Grid::from(&req.headers);
+                    self.mode = RequestMode::Insert(Editable::new(vec![""]), Field::Headers)
+                }
+                Field::Headers => self.mode = self.body_field(&req),
+                Field::Body | Field::Form => {
+                    self.mode = RequestMode::Insert(self.editables[3].clone(), Field::Extract)
+                }
+                Field::Extract => {
+                    self.mode = RequestMode::Insert(self.editables[5].clone(), Field::Asserts)
+                }
+                Field::Asserts => {
                     self.mode = RequestMode::Insert(self.editables[0].clone(), Field::Desc)
                 }
             }
         }
     }
 
+    /// Form and multipart bodies are edited in the grid, the others as text.
+    fn body_field(&mut self, req: &Request) -> RequestMode {
+        match req.body_mode {
+            BodyMode::Form | BodyMode::Multipart => {
+                self.grid = Grid::from(&req.form);
+                RequestMode::Insert(Editable::new(vec![""]), Field::Form)
+            }
+            _ => RequestMode::Insert(self.editables[2].clone(), Field::Body),
+        }
+    }
+
+    /// Saves the body and switches it to the next body mode.
+    pub fn next_body_mode(&mut self, state: &mut State) {
+        if !matches!(self.mode, RequestMode::Insert(_, Field::Body | Field::Form)) {
+            return;
+        }
+        self.save(state);
+        let Some(mut req) = self.selected_request() else {
+            return;
+        };
+        req.body_mode = req.body_mode.next();
+        req.touch();
+        self.data.add_request(req.clone());
+        state.insert(self.data.clone());
+        state.save();
+        self.editables = self.editables();
+        self.mode = self.body_field(&req);
+    }
+
     pub fn save(&mut self, state: &mut State) {
         if let RequestMode::Insert(editable, field) = &self.mode {
-            let idx = self.state.selected().unwrap_or(0);
-            let req = &mut self.data.requests.clone().into_iter().collect::<Vec<_>>()[idx];
+            let Some(mut req) = self.selected_request() else {
+                return;
+            };
+            let original = req.clone();
 
             match field {
                 Field::Desc => {
@@ -236,59 +832,120 @@ impl RequestView {
                 Field::Path => {
                     let path = editable.input.clone().into_iter().next().unwrap_or_default(); 
                     req.path = path;
+                    req.split_query();
                 },
+                Field::Query => {
+                    req.query = Vec::<Param>::from(self.grid.clone());
+                }
                 Field::Headers => {
-                    let headers = BTreeSet::<Header>::from(editable.clone());
-                    req.headers = headers;
+                    req.headers = Vec::<Header>::from(self.grid.clone());
+                }
+                Field::Form => {
+                    req.form = Vec::<Param>::from(self.grid.clone());
                 }
                 Field::Body => {
                     let body = editable.input.clone().into_iter().next().unwrap_or_default(); 
                     req.body = body;
                 },
+                Field::Extract => {
+                    req.extract = Vec::<Extract>::from(editable.clone());
+                },
+                Field::Tags => {
+                    let tags = editable.input.join(" ");
+                    req.tags = tags.split_whitespace().map(String::from).collect();
+                },
+                Field::Asserts => {
+                    req.asserts = Vec::<Assert>::from(editable.clone());
+                },
+                Field::Folder => {
+                    let folder = editable
+                        .input
+                        .clone()
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default();
+                    req.folder = folder;
+                },
+                Field::Settings => match Settings::parse(&editable.input.join(" ")) {
+                    Ok(settings) => req.settings = settings,
+                    Err(err) => self.message = Some(format!("invalid settings: {err}")),
+                },
+            }
+            // editing a changed operation counts as reviewing it
+            if let Some(op) = req
+                .operation
+                .as_mut()
+                .filter(|op| op.status == SyncStatus::Changed)
+            {
+                op.status = SyncStatus::Synced;
+            }
+            if req != original {
+                req.touch();
             }
             self.mode = RequestMode::Normal;
-            self.data.requests.insert(req.clone());
             self.data.add_request(req.clone());
+            self.select_request(&req);
             state.insert(self.data.clone());
             state.save();
         }
     }
 
     pub fn render_editor(&mut self, frame: &mut Frame) {
-        if let RequestMode::Insert(editable, field) = &mut self.mode {
-            let idx = self.state.selected().unwrap_or(0);
-            let req = self.data.requests.iter().collect::<Vec<_>>()[idx];
+        let Some(idx) = self.selected_index() else {
+            return;
+        };
+        let req = &self.data.requests[idx];
 
-            let area = centered_rect(80, 40, frame.size());
+        if let RequestMode::Insert(editable, field) = &mut self.mode {
+            let area = centered_rect(80, 80, frame.size());
 
             let layout = Layout::vertical([
                 Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Min(0),
+                Constraint::Length(3),
+                Constraint::Min(0),
                 Constraint::Fill(1),
+                Constraint::Min(0),
+                Constraint::Min(0),
             ]);
 
             frame.render_widget(Clear, area); // clear screen under the popup
-            let [area_host, area_desc, area_path, area_head, area_body] = layout.areas(area);
-            let idx = self.state.selected().unwrap_or(0);
+            let [area_host, area_desc, area_path, area_query, area_group, area_head, area_body, area_extract, area_asserts] =
+                layout.areas(area);
+            let [area_folder, area_tags, area_settings] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ])
+            .areas(area_group);
+            let border = self.theme.fields[idx % self.theme.fields.len()];
 
-            // HOST
-            let block = Block::bordered()
-                .title("Host")
-                .border_style(PALETTES[idx].c700);
-            let host = self
-                .data
-                .hosts
-                .first()
-                .expect("app.host in requests view");
-            let host = Paragraph::new(host.to_owned()).block(block);
+            // URL
+            let mut preview = req.clone();
+            match field {
+                Field::Path => {
+                    preview.path = editable.input.first().cloned().unwrap_or_default();
+                    preview.split_query();
+                }
+                Field::Query => preview.query = Vec::<Param>::from(self.grid.clone()),
+                _ => {}
+            }
+            let preview = vars::resolve(&preview, &self.data.vars);
+            // apps only in the workspace have no hosts until they are refreshed
+            let (title, url) = match self.data.host() {
+                Some(host) => ("URL", export::url(host, &preview.path_with_query())),
+                None => ("URL (no host)", preview.path_with_query()),
+            };
+            let block = Block::bordered().title(title).border_style(border);
+            let host = Paragraph::new(url).block(block);
             frame.render_widget(host, area_host);
 
             // DESC
             let block = Block::bordered()
                 .title("Description")
-                .border_style(PALETTES[idx].c700);
+                .border_style(border);
             let desc = if field == &Field::Desc {
                 editable
                     .input
@@ -301,9 +958,7 @@ impl RequestView {
             frame.render_widget(desc, area_desc);
 
             // PATH
-            let block = Block::bordered()
-                .title("Path")
-                .border_style(PALETTES[idx].c700);
+            let block = Block::bordered().title("Path").border_style(border);
             let path = if field == &Field::Path {
                 editable
                     .input
@@ -314,161 +969,503 @@ impl RequestView {
             } else {
                 req.path.clone()
             };
-            let path = Paragraph::new(path).block(block);
-            frame.render_widget(path, area_path);
+            let path = Paragraph::new(path).block(block);
+            frame.render_widget(path, area_path);
+
+            // QUERY
+            let query = match field {
+                Field::Query => self.grid.items(true),
+                _ => Grid::from(&req.query).items(false),
+            };
+            let block = Block::bordered()
+                .title(format!(
+                    "Query {} enable/disable",
+                    keymap::current().hint(Context::Editor, Action::ToggleEnabled)
+                ))
+                .border_style(border);
+            let query = List::new(query).block(block);
+            frame.render_widget(query, area_query);
+
+            // FOLDER
+            let block = Block::bordered()
+                .title("Folder <parent>/<child>")
+                .border_style(border);
+            let folder = if field == &Field::Folder {
+                editable.input.join("")
+            } else {
+                req.folder.clone()
+            };
+            let folder = Paragraph::new(folder).block(block);
+            frame.render_widget(folder, area_folder);
+
+            // TAGS
+            let block = Block::bordered().title("Tags").border_style(border);
+            let tags = if field == &Field::Tags {
+                editable.input.join(" ")
+            } else {
+                req.tags.iter().join(" ")
+            };
+            let tags = Paragraph::new(tags).block(block);
+            frame.render_widget(tags, area_tags);
+
+            // SETTINGS
+            let title = match self.data.settings.to_string() {
+                defaults if defaults.is_empty() => {
+                    "Settings timeout= connect= retries= redirects=".into()
+                }
+                defaults => format!("Settings (app {defaults})"),
+            };
+            let block = Block::bordered().title(title).border_style(border);
+            let settings = if field == &Field::Settings {
+                editable.input.join(" ")
+            } else {
+                req.settings.to_string()
+            };
+            let settings = Paragraph::new(settings).block(block);
+            frame.render_widget(settings, area_settings);
+
+            // HEADER
+            let headers = match field {
+                Field::Headers => self.grid.items(true),
+                _ => Grid::from(&req.headers).items(false),
+            };
+            let block = Block::bordered()
+                .title(format!(
+                    "Headers {} enable/disable {} secret",
+                    keymap::current().hint(Context::Editor, Action::ToggleEnabled),
+                    keymap::current().hint(Context::Editor, Action::ToggleSecret)
+                ))
+                .border_style(border);
+            let headers = List::new(headers).block(block);
+            frame.render_widget(headers, area_head);
+
+            // BODY
+            let body: Vec<ListItem> = match (&field, req.body_mode) {
+                (Field::Form, _) => self.grid.items(true),
+                (_, BodyMode::Form | BodyMode::Multipart) => Grid::from(&req.form).items(false),
+                (Field::Body, _) => editable
+                    .input
+                    .iter()
+                    .map(|m| ListItem::new(Line::from(Span::raw(m))))
+                    .collect(),
+                _ => req
+                    .body
+                    .split('\n')
+                    .map(|m| ListItem::new(Line::from(Span::raw(m))).clone())
+                    .collect(),
+            };
+            let mut checked = vars::resolve(req, &self.data.vars);
+            if field == &Field::Body {
+                checked.body = vars::substitute(&editable.input.join("\n"), &self.data.vars);
+            }
+            let violations = match &self.data.openapi {
+                Some(doc) => openapi::validate_request(doc, &checked),
+                None => vec![],
+            };
+            let block = match violations.first() {
+                Some(first) => Block::bordered()
+                    .title(format!(
+                        "Body ({} schema violations, {first})",
+                        violations.len()
+                    ))
+                    .border_style(self.theme.error),
+                None => {
+                    let content_type = req.content_type().unwrap_or("multipart/form-data".into());
+                    let hint = match req.body_mode {
+                        BodyMode::Multipart => " <key> <value|@file>",
+                        BodyMode::File => " <path>",
+                        _ => "",
+                    };
+                    Block::bordered()
+                        .title(format!(
+                            "Body {} ({content_type}){hint}, {} mode",
+                            req.body_mode,
+                            keymap::current().hint(Context::Editor, Action::NextBodyMode)
+                        ))
+                        .border_style(border)
+                }
+            };
+            let body = List::new(body).block(block);
+            frame.render_widget(body, area_body);
+            // let body = Paragraph::new(body).block(block);
+            // frame.render_widget(body, area_body);
 
-            // HEADER
-            let headers: Vec<ListItem> = if field == &Field::Headers {
+            // EXTRACT
+            let extract: Vec<ListItem> = if field == &Field::Extract {
                 editable
                     .input
                     .iter()
                     .map(|m| ListItem::new(Line::from(Span::raw(m))))
                     .collect()
             } else {
-                self.data.requests.iter().collect::<Vec<_>>()[idx]
-                    .headers
-                    .iter()
-                    .map(|h| format!("{:<15} {}", h.key, h.value))
+                Editable::from(&req.extract)
+                    .input
+                    .into_iter()
                     .map(|m| ListItem::new(Line::from(Span::raw(m))))
                     .collect()
             };
             let block = Block::bordered()
-                .title("Headers")
-                .border_style(PALETTES[idx].c700);
-            let headers = List::new(headers).block(block);
-            frame.render_widget(headers, area_head);
+                .title("Extract <var> <json|header|regex> <expr>")
+                .border_style(border);
+            let extract = List::new(extract).block(block);
+            frame.render_widget(extract, area_extract);
 
-            // BODY
-            let body: Vec<ListItem> = if field == &Field::Body {
+            // ASSERTS
+            let asserts: Vec<ListItem> = if field == &Field::Asserts {
                 editable
                     .input
                     .iter()
                     .map(|m| ListItem::new(Line::from(Span::raw(m))))
                     .collect()
             } else {
-                req.body
-                    .split('\n')
-                    .map(|m| ListItem::new(Line::from(Span::raw(m))).clone())
+                Editable::from(&req.asserts)
+                    .input
+                    .into_iter()
+                    .map(|m| ListItem::new(Line::from(Span::raw(m))))
                     .collect()
             };
             let block = Block::bordered()
-                .title("Body")
-                .border_style(PALETTES[idx].c700);
-            let body = List::new(body).block(block);
-            frame.render_widget(body, area_body);
-            // let body = Paragraph::new(body).block(block);
-            // frame.render_widget(body, area_body);
+                .title("Asserts status <code> | header <name> | time <ms> | json <path> <==|~> <value>")
+                .border_style(border);
+            let asserts = List::new(asserts).block(block);
+            frame.render_widget(asserts, area_asserts);
 
             // CURSOR
             let cursor_area = match field {
                 Field::Desc => area_desc,
                 Field::Path => area_path,
                 Field::Headers => area_head,
-                Field::Body => area_body,
+                Field::Body | Field::Form => area_body,
+                Field::Extract => area_extract,
+                Field::Tags => area_tags,
+                Field::Folder => area_folder,
+                Field::Settings => area_settings,
+                Field::Asserts => area_asserts,
+                Field::Query => area_query,
             };
-            frame.set_cursor(
-                cursor_area.x + editable.x as u16 + 1,
-                cursor_area.y + editable.y as u16 + 1,
-            );
+            match field {
+                Field::Headers | Field::Query | Field::Form => {
+                    let (x, y) = self.grid.cursor();
+                    frame.set_cursor(cursor_area.x + x as u16 + 1, cursor_area.y + y as u16 + 1)
+                }
+                _ => frame.set_cursor(
+                    cursor_area.x + editable.x as u16 + 1,
+                    cursor_area.y + editable.y as u16 + 1,
+                ),
+            }
         }
     }
 }
 
-const PALETTES: [Palette; 7] = [
-    tailwind::RED,
-    tailwind::YELLOW,
-    tailwind::GREEN,
-    tailwind::BLUE,
-    tailwind::INDIGO,
-    tailwind::PURPLE,
-    tailwind::PINK,
-];
+fn search_text(req: &Request) -> String {
+    format!("{} {}", req.path, req.desc)
+}
+
+/// The tree items, with the chars matching the search highlighted.
+fn tree_items(nodes: &[Node], search: &Search) -> Vec<TreeItem<'static, String>> {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Folder(name, children) => {
+                let id = format!("{FOLDER}{name}");
+                let text = Line::from(format!("{name}/")).bold();
+                TreeItem::new(id.clone(), text.clone(), tree_items(children, search))
+                    .unwrap_or_else(|_| TreeItem::new_leaf(id, text))
+            }
+            Node::Request(req) => {
+                // the search matches `<path> <desc>`, see `RequestView::tree`
+                let positions = search
+                    .matches(&search_text(req))
+                    .map(|m| m.positions)
+                    .unwrap_or_default();
+                let path_len = req.path.chars().count();
+                let (in_path, in_desc) = positions
+                    .into_iter()
+                    .partition::<Vec<usize>, _>(|&i| i < path_len);
+                let in_desc = in_desc
+                    .iter()
+                    .filter_map(|i| i.checked_sub(path_len + 1))
+                    .collect::<Vec<_>>();
+
+                let mut spans = vec![Span::from(format!("{:<8}", req.method.to_string())).bold()];
+                spans.extend(highlight(&format!("{:<41}", req.path), &in_path, match_style()));
+                match req.operation.as_ref().map(|op| op.status) {
+                    Some(SyncStatus::Changed) => spans.push("[changed] ".fg(theme::current().warning)),
+                    Some(SyncStatus::Removed) => spans.push("[removed] ".fg(theme::current().error)),
+                    _ => {}
+                }
+                if req.shared {
+                    spans.push("[shared] ".fg(theme::current().info));
+                }
+                spans.extend(highlight(&req.desc, &in_desc, match_style()));
+                let text = Line::from(spans);
+                TreeItem::new_leaf(format!("{REQUEST}{}", req.id), text)
+            }
+        })
+        .collect()
+}
+
+impl RequestView {
+    fn render_import(&mut self, frame: &mut Frame) {
+        let RequestMode::Import(editable) = &self.mode else {
+            return;
+        };
+
+        let area = centered_rect(80, 60, frame.size());
+        let lines = editable
+            .input
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect::<Vec<_>>();
+        let block = Block::bordered()
+            .title("Import curl (paste or type, Esc to import)")
+            .border_style(self.theme.header_boarder);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        frame.set_cursor(
+            area.x + editable.x as u16 + 1,
+            area.y + editable.y as u16 + 1,
+        );
+    }
+}
+
+impl RequestView {
+    fn render_save(&mut self, frame: &mut Frame) {
+        let (editable, title, confirm) = match &self.mode {
+            RequestMode::Save(editable, Save::Response) => (editable, "Save response as", "save"),
+            RequestMode::Save(editable, Save::Download) => (editable, "Download to", "start"),
+            RequestMode::Load(editable) => (
+                editable,
+                "Load n=count or t=time, c=concurrency, rps=rate",
+                "start",
+            ),
+            _ => return,
+        };
+        let keys = keymap::current();
+        let title = format!(
+            "{title} {} {confirm} {} cancel",
+            keys.hint(Context::Prompt, Action::Confirm),
+            keys.hint(Context::Prompt, Action::Close)
+        );
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_lay = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
+        let area = centered_rect(60, 20, frame.size());
+        let [area] = Layout::vertical([Constraint::Length(3)]).areas(area);
+        let block = Block::bordered()
+            .title(title)
+            .border_style(self.theme.header_boarder);
 
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_lay[1])[1]
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(editable.input.join("")).block(block), area);
+        frame.set_cursor(area.x + editable.x as u16 + 1, area.y + 1);
+    }
 }
 
 impl RequestView {
-    fn render_table(&mut self, frame: &mut Frame, layout: Rect) {
-        let header_style = Style::default()
-            .fg(self.theme.header_fg)
-            .bg(self.theme.header_bg);
+    fn render_export(&mut self, frame: &mut Frame) {
+        let (Some(text), RequestMode::Export(format, _)) = (self.exported(), &self.mode) else {
+            return;
+        };
+
+        let area = centered_rect(80, 60, frame.size());
+        let keys = keymap::current();
+        let title = [
+            Action::NextFormat,
+            Action::ToggleToken,
+            Action::Copy,
+            Action::Write,
+            Action::Close,
+        ]
+        .into_iter()
+        .fold(format!("Export {format}"), |title, action| {
+            let hint = keys.hint(Context::Export, action);
+            format!("{title} {hint} {}", action.label())
+        });
+        let block = Block::bordered()
+            .title(title)
+            .border_style(self.theme.header_boarder);
 
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(text).block(block), area);
+    }
+}
+
+/// The phases of the request, like `ttfb 120 ms  total 125 ms  probe dns 2 ms connect 10 ms
+/// tls 31 ms`. DNS, connect and TLS are from the probe's own connection, see `client::probe`.
+fn timing(res: &Response) -> String {
+    let ms = |d: Duration| format!("{} ms", d.as_millis());
+    let request = format!("ttfb {}  total {}", ms(res.timing.ttfb), ms(res.elapsed));
+    let probe = [
+        ("dns", res.timing.dns),
+        ("connect", res.timing.connect),
+        ("tls", res.timing.tls),
+    ]
+    .into_iter()
+    .filter_map(|(phase, d)| Some(format!("{phase} {}", ms(d?))))
+    .join(" ");
+    match probe.is_empty() {
+        true => request,
+        false => format!("{request}  probe {probe}"),
+    }
+}
+
+/// The sizes of the headers and bodies sent and received.
+fn sizes(res: &Response) -> String {
+    let sizes = &res.sizes;
+    let encoding = match (&sizes.encoding, sizes.decoded_body) {
+        (Some(encoding), Some(decoded)) => {
+            format!(" ({encoding}, {} decompressed)", human_size(decoded))
+        }
+        (Some(encoding), None) => format!(" ({encoding} compressed)"),
+        (None, _) => String::new(),
+    };
+    format!(
+        "sent {} headers {} body  received {} headers {} body{encoding}",
+        human_size(sizes.request_headers),
+        human_size(sizes.request_body),
+        human_size(sizes.response_headers),
+        human_size(sizes.response_body),
+    )
+}
+
+impl RequestView {
+    fn render_tree(&mut self, frame: &mut Frame, layout: Rect) {
         let selected_style = Style::default()
             .add_modifier(Modifier::REVERSED)
             .fg(self.theme.selected_style_fg);
 
-        let header = ["METHOD", "PATH", "DESC"]
-            .into_iter()
-            .map(Cell::from)
-            .collect::<Row>()
-            .style(header_style)
-            .height(1);
-
-        let rows = self.data.requests.iter().enumerate().map(|(i, data)| {
-            let color = match i % 2 {
-                0 => self.theme.normal_row,
-                _ => self.theme.alt_row,
-            };
+        let items = tree_items(&self.tree(), &self.search);
+        let Ok(tree) = Tree::new(&items) else {
+            let error = Paragraph::new("duplicate request ids").fg(self.theme.error);
+            frame.render_widget(error, layout);
+            return;
+        };
+
+        let tree = tree
+            .block(
+                Block::bordered()
+                    .title(Line::from(format!(
+                        "{:5}{:<8}{:<41}{}",
+                        "", "METHOD", "PATH", "DESC"
+                    )))
+                    .title_style(
+                        Style::new()
+                            .fg(self.theme.header_fg)
+                            .bg(self.theme.header_bg),
+                    )
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            )
+            .experimental_scrollbar(Some(
+                Scrollbar::default()
+                    .orientation(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None),
+            ))
+            .style(Style::new().fg(self.theme.row_fg).bg(self.theme.buffer_bg))
+            .highlight_style(selected_style)
+            .highlight_symbol(" █ ");
 
-            let method = format!("{}", &data.method);
-            let columns = vec![&method, &data.path, &data.desc];
+        frame.render_stateful_widget(tree, layout, &mut self.state)
+    }
 
-            columns
-                .into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(self.theme.row_fg).bg(color))
-                .height(4)
-        });
+    /// The latency of the last responses of the selected request, the newest to the right.
+    fn render_latency(&mut self, frame: &mut Frame, layout: Rect, history: &[Sample]) {
+        if history.len() < 2 {
+            return;
+        }
 
-        let bar = " █ ";
-        let t = Table::new(
-            rows,
-            [
-                Constraint::Length(self.max_len.0 + 1),
-                Constraint::Length(self.max_len.1 + 1),
-                Constraint::Length(self.max_len.2 + 1),
-            ],
-        )
-        .header(header)
-        .highlight_style(selected_style)
-        .highlight_symbol(Text::from(vec![
-            "".into(),
-            bar.into(),
-            bar.into(),
-            "".into(),
-        ]))
-        .bg(self.theme.buffer_bg)
-        .highlight_spacing(HighlightSpacing::Always);
-
-        frame.render_stateful_widget(t, layout, &mut self.state)
-    }
-
-    fn render_scrollbar(&mut self, frame: &mut Frame, layout: Rect) {
-        frame.render_stateful_widget(
-            Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(None)
-                .end_symbol(None),
-            layout.inner(Margin {
-                vertical: 1,
-                horizontal: 1,
-            }),
-            &mut self.scroll_state,
-        );
+        let width = layout.width.saturating_sub(2) as usize;
+        let recent = &history[history.len().saturating_sub(width)..];
+        let data = recent.iter().map(|s| s.ms).collect::<Vec<_>>();
+        let min = data.iter().min().unwrap_or(&0);
+        let max = data.iter().max().unwrap_or(&0);
+        let last = data.last().unwrap_or(&0);
+
+        let sparkline = Sparkline::default()
+            .block(
+                Block::bordered()
+                    .title(format!(
+                        "Latency last {} ms, min {min} max {max} of {}",
+                        last,
+                        recent.len()
+                    ))
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            )
+            .data(&data)
+            .style(Style::new().fg(self.theme.header_fg).bg(self.theme.buffer_bg));
+        frame.render_widget(sparkline, layout);
+    }
+
+    fn render_response(&mut self, frame: &mut Frame, layout: Rect) {
+        let Some(outcome) = &self.response else {
+            return;
+        };
+
+        let lines: Vec<Line> = match outcome {
+            Outcome::Done(res, extracted, violations) => {
+                let mut lines = vec![
+                    Line::from(format!("{} ({} ms)", res.status, res.elapsed.as_millis())),
+                    Line::from(timing(res)).fg(self.theme.muted),
+                    Line::from(sizes(res)).fg(self.theme.muted),
+                ];
+
+                lines.extend(extracted.iter().map(|(var, value)| match value {
+                    Some(value) => Line::from(format!("{var} = {value}")),
+                    None => Line::from(format!("{var} = <no match>")).fg(self.theme.error),
+                }));
+
+                if !violations.is_empty() {
+                    lines.push(Line::from("Response does not match the OpenAPI schema:").bold());
+                    lines.extend(
+                        violations
+                            .iter()
+                            .map(|v| Line::from(v.to_string()).fg(self.theme.error)),
+                    );
+                }
+
+                lines.push(Line::default());
+                if res.is_binary() {
+                    let content_type = res.header("content-type").unwrap_or("unknown type");
+                    let size = human_size(res.bytes.len() as u64);
+                    let save = keymap::current().hint(Context::Requests, Action::SaveAs);
+                    lines.push(Line::from(format!("{content_type}, {size}, {save} to save")).bold());
+                    lines.extend(hexdump(&res.bytes, 512).into_iter().map(Line::from));
+                } else {
+                    let body = match serde_json::from_str::<serde_json::Value>(&res.body) {
+                        Ok(json) => serde_json::to_string_pretty(&json).unwrap_or(res.body.clone()),
+                        Err(_) => res.body.clone(),
+                    };
+                    lines.extend(body.lines().map(|l| Line::from(l.to_string())));
+                }
+                lines
+            }
+            Outcome::Invalid(_, violations) => {
+                let send = keymap::current().hint(Context::Requests, Action::Send);
+                let mut lines = vec![Line::from(format!(
+                    "Request body does not match the OpenAPI schema, {send} send again to send anyway:",
+                ))
+                .bold()];
+                lines.extend(
+                    violations
+                        .iter()
+                        .map(|v| Line::from(v.to_string()).fg(self.theme.error)),
+                );
+                lines
+            }
+            Outcome::Failed(err) => err
+                .lines()
+                .map(|l| Line::from(l.to_string()).fg(self.theme.error))
+                .collect(),
+        };
+
+        let response = Paragraph::new(lines)
+            .style(Style::new().fg(self.theme.row_fg).bg(self.theme.buffer_bg))
+            .block(
+                Block::bordered()
+                    .title("Response")
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            );
+        frame.render_widget(response, layout);
     }
 
     fn render_header(&mut self, frame: &mut Frame, layout: Rect) {
@@ -488,7 +1485,54 @@ impl RequestView {
     }
 
     fn render_footer(&mut self, frame: &mut Frame, layout: Rect) {
-        let info_footer = Paragraph::new(Line::from(INFO_TEXT))
+        if let Some((progress, _)) = &self.download {
+            let progress = progress.lock().expect("download progress");
+            let path = progress.path.display();
+            match &progress.done {
+                None => {
+                    let received = human_size(progress.received);
+                    let (ratio, label) = match progress.total {
+                        Some(total) if total > 0 => (
+                            (progress.received as f64 / total as f64).min(1.0),
+                            format!("{path} {received} of {}", human_size(total)),
+                        ),
+                        _ => (0.0, format!("{path} {received}")),
+                    };
+                    let gauge = Gauge::default()
+                        .block(
+                            Block::bordered()
+                                .border_type(BorderType::Double)
+                                .border_style(Style::new().fg(self.theme.footer_boarder)),
+                        )
+                        .gauge_style(
+                            Style::new()
+                                .fg(self.theme.footer_boarder)
+                                .bg(self.theme.buffer_bg),
+                        )
+                        .ratio(ratio)
+                        .label(label);
+                    frame.render_widget(gauge, layout);
+                    return;
+                }
+                Some(Ok(bytes)) => {
+                    let status = progress.status.unwrap_or_default();
+                    let size = human_size(*bytes);
+                    self.message = Some(format!("{status}: saved {size} to {path}"));
+                }
+                Some(Err(err)) => self.message = Some(format!("download failed: {err}")),
+            }
+            drop(progress);
+            self.download = None;
+        }
+
+        let text = match (&self.message, self.search.footer()) {
+            (_, Some(search)) if self.search.typing => search,
+            (Some(message), _) => message.clone(),
+            (None, Some(search)) => search,
+            (None, None) => keymap::current().footer(Context::Requests),
+        };
+
+        let info_footer = Paragraph::new(Line::from(text))
             .style(
                 Style::new()
                     .fg(self.theme.footer_boarder)
@@ -512,6 +1556,15 @@ pub struct Editable {
 }
 
 impl Editable {
+    /// A single line with the cursor at its end.
+    fn at_end(line: String) -> Self {
+        Self {
+            x: line.chars().count(),
+            input: vec![line],
+            y: 0,
+        }
+    }
+
     pub fn new<T>(input: Vec<T>) -> Self
     where
         T: ToString,
@@ -524,30 +1577,79 @@ impl Editable {
     }
 }
 
-impl From<&BTreeSet<Header>> for Editable {
-    fn from(value: &BTreeSet<Header>) -> Self {
-        Self {
-            input: value
+impl From<&Vec<Extract>> for Editable {
+    fn from(value: &Vec<Extract>) -> Self {
+        let input = match value.is_empty() {
+            true => vec![String::new()],
+            false => value
                 .iter()
-                .map(|h| format!("{:<15} {}", h.key, h.value))
+                .map(|e| format!("{:<15} {}", e.var, e.from))
                 .collect(),
-            x: 0,
-            y: 0,
-        }
+        };
+
+        Self { input, x: 0, y: 0 }
+    }
+}
+
+impl From<Editable> for Vec<Extract> {
+    fn from(value: Editable) -> Self {
+        value
+            .input
+            .iter()
+            .filter_map(|line| {
+                let mut split = line.split_whitespace();
+                let var = split.next()?;
+                let kind = split.next()?;
+                let expr = split.collect::<Vec<&str>>().join(" ");
+                let from = match kind {
+                    "json" => Source::JsonPath(expr),
+                    "header" => Source::Header(expr),
+                    "regex" => Source::Regex(expr),
+                    _ => return None,
+                };
+                Some(Extract::new(var, from))
+            })
+            .collect()
+    }
+}
+
+impl From<&Vec<Assert>> for Editable {
+    fn from(value: &Vec<Assert>) -> Self {
+        let input = match value.is_empty() {
+            true => vec![String::new()],
+            false => value.iter().map(|a| a.to_string()).collect(),
+        };
+
+        Self { input, x: 0, y: 0 }
     }
 }
 
-impl From<Editable> for BTreeSet<Header> {
+impl From<Editable> for Vec<Assert> {
     fn from(value: Editable) -> Self {
         value
             .input
             .iter()
-            .map(|h| {
-                let mut split = h.split_whitespace();
-                Header::new(
-                    split.next().unwrap_or_default().to_string(),
-                    split.collect::<Vec<&str>>().join(" "),
-                )
+            .filter_map(|line| {
+                let (kind, rest) = line.trim().split_once(' ')?;
+                let rest = rest.trim();
+                match kind {
+                    "status" => rest.parse().ok().map(Assert::Status),
+                    "header" => Some(Assert::Header(rest.into())),
+                    "time" => rest.parse().ok().map(Assert::TimeUnder),
+                    "json" => {
+                        let (path, rest) = rest.split_once(' ')?;
+                        match rest.trim().split_once(' ') {
+                            Some(("==", value)) => {
+                                Some(Assert::JsonEquals(path.into(), value.trim().into()))
+                            }
+                            Some(("~", pattern)) => {
+                                Some(Assert::JsonMatches(path.into(), pattern.trim().into()))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
             })
             .collect()
     }
@@ -641,6 +1743,286 @@ impl Editing for Editable {
     }
 }
 
+/// Key/value rows edited as two columns, so keys and values may contain any character.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Grid {
+    pub rows: Vec<GridRow>,
+    x: usize,
+    y: usize,
+    col: usize,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct GridRow {
+    pub key: String,
+    pub value: String,
+    pub enabled: bool,
+    pub secret: bool,
+}
+
+impl Default for GridRow {
+    fn default() -> Self {
+        Self {
+            key: String::new(),
+            value: String::new(),
+            enabled: true,
+            secret: false,
+        }
+    }
+}
+
+const MASK: &str = "••••••••";
+
+impl Grid {
+    fn new(rows: Vec<GridRow>) -> Self {
+        let rows = match rows.is_empty() {
+            true => vec![GridRow::default()],
+            false => rows,
+        };
+        Self {
+            rows,
+            x: 0,
+            y: 0,
+            col: 0,
+        }
+    }
+
+    pub fn toggle_enabled(&mut self) {
+        if let Some(row) = self.rows.get_mut(self.y) {
+            row.enabled = !row.enabled;
+        }
+    }
+
+    pub fn toggle_secret(&mut self) {
+        if let Some(row) = self.rows.get_mut(self.y) {
+            row.secret = !row.secret;
+        }
+    }
+
+    fn cell(&mut self) -> &mut String {
+        let row = &mut self.rows[self.y];
+        match self.col {
+            0 => &mut row.key,
+            _ => &mut row.value,
+        }
+    }
+
+    fn cell_len(&self) -> usize {
+        let row = &self.rows[self.y];
+        match self.col {
+            0 => row.key.chars().count(),
+            _ => row.value.chars().count(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|r| r.key.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(15)
+    }
+
+    /// Rows as `[x] key value`, with disabled rows dimmed and secrets masked outside the edited row.
+    fn items(&self, editing: bool) -> Vec<ListItem<'static>> {
+        let width = self.width();
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| editing || !row.key.is_empty())
+            .map(|(y, row)| {
+                let check = match row.enabled {
+                    true => "[x]",
+                    false => "[ ]",
+                };
+                let value = match row.secret && !(editing && y == self.y) {
+                    true => MASK.to_string(),
+                    false => row.value.clone(),
+                };
+                let line = format!("{check} {:<width$} {value}", row.key);
+                match row.enabled {
+                    true => ListItem::new(Line::from(Span::raw(line))),
+                    false => ListItem::new(Line::from(Span::raw(line).dim())),
+                }
+            })
+            .collect()
+    }
+
+    /// The cursor position relative to the inside of the block.
+    fn cursor(&self) -> (usize, usize) {
+        let offset = match self.col {
+            0 => 4,
+            _ => 4 + self.width() + 1,
+        };
+        (offset + self.x, self.y)
+    }
+}
+
+impl From<&Vec<Header>> for Grid {
+    fn from(value: &Vec<Header>) -> Self {
+        Grid::new(
+            value
+                .iter()
+                .map(|h| GridRow {
+                    key: h.key.clone(),
+                    value: h.value.clone(),
+                    enabled: h.enabled,
+                    secret: h.secret,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<Grid> for Vec<Header> {
+    fn from(value: Grid) -> Self {
+        value
+            .rows
+            .into_iter()
+            .filter(|r| !r.key.trim().is_empty())
+            .map(|r| Header {
+                enabled: r.enabled,
+                secret: r.secret,
+                ..Header::new(r.key.trim().into(), r.value)
+            })
+            .collect()
+    }
+}
+
+impl From<&Vec<Param>> for Grid {
+    fn from(value: &Vec<Param>) -> Self {
+        Grid::new(
+            value
+                .iter()
+                .map(|p| GridRow {
+                    key: p.key.clone(),
+                    value: p.value.clone(),
+                    enabled: p.enabled,
+                    secret: false,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<Grid> for Vec<Param> {
+    fn from(value: Grid) -> Self {
+        value
+            .rows
+            .into_iter()
+            .filter(|r| !r.key.trim().is_empty())
+            .map(|r| Param {
+                enabled: r.enabled,
+                ..Param::new(r.key.trim(), &r.value)
+            })
+            .collect()
+    }
+}
+
+impl Editing for Grid {
+    fn new_line(&mut self) {
+        self.rows.insert(self.y + 1, GridRow::default());
+        self.y += 1;
+        self.x = 0;
+        self.col = 0;
+    }
+
+    fn move_cursor(&mut self, dir: Direction, steps: usize) {
+        match dir {
+            Direction::Up => {
+                self.y = self.y.saturating_sub(steps);
+                self.x = self.x.min(self.cell_len());
+            }
+            Direction::Down => {
+                self.y = self.y.saturating_add(steps).min(self.rows.len() - 1);
+                self.x = self.x.min(self.cell_len());
+            }
+            // moving past the start of the value continues at the end of the key
+            Direction::Left if self.x == 0 && self.col == 1 => {
+                self.col = 0;
+                self.x = self.cell_len();
+            }
+            Direction::Left => self.x = self.x.saturating_sub(steps),
+            // moving past the end of the key continues at the start of the value
+            Direction::Right if self.x == self.cell_len() && self.col == 0 => {
+                self.col = 1;
+                self.x = 0;
+            }
+            Direction::Right => self.x = self.x.saturating_add(steps).min(self.cell_len()),
+        }
+    }
+
+    fn add_char(&mut self, c: char) {
+        // keys can't contain whitespace, so it moves on to the value
+        if self.col == 0 && c.is_whitespace() {
+            self.col = 1;
+            self.x = 0;
+            return;
+        }
+        let x = self.x;
+        let cell = self.cell();
+        let idx = cell
+            .char_indices()
+            .map(|(i, _)| i)
+            .nth(x)
+            .unwrap_or(cell.len());
+        cell.insert(idx, c);
+        self.x += 1;
+    }
+
+    fn del_char(&mut self) {
+        match (self.x, self.col) {
+            (x, _) if x != 0 => {
+                let cell = self.cell();
+                *cell = cell
+                    .chars()
+                    .take(x - 1)
+                    .chain(cell.chars().skip(x))
+                    .collect();
+                self.x -= 1;
+            }
+            (_, 1) => {
+                self.col = 0;
+                self.x = self.cell_len();
+            }
+            // an empty row is removed when deleting from its start
+            _ if self.rows.len() > 1 && self.rows[self.y] == GridRow::default() => {
+                self.rows.remove(self.y);
+                self.y = self.y.saturating_sub(1);
+                self.col = 1;
+                self.x = self.cell_len();
+            }
+            _ => {}
+        }
+    }
+
+    /// Pasted lines are read as `key: value`, `key=value` or `key value`, one row each.
+    fn paste(&mut self, text: &str) {
+        for line in text
+            .split(['\r', '\n'])
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            let separator = line.find(|c: char| c == ':' || c == '=' || c.is_whitespace());
+            let (key, value) = match separator {
+                Some(idx) => {
+                    let (key, rest) = line.split_at(idx);
+                    (key, rest.trim_start_matches([':', '=', ' ']))
+                }
+                None => (line, ""),
+            };
+            if self.rows[self.y] != GridRow::default() {
+                self.new_line();
+            }
+            self.rows[self.y].key = key.trim().into();
+            self.rows[self.y].value = value.trim().into();
+            self.col = 1;
+            self.x = self.cell_len();
+        }
+    }
+}
+
 pub enum Direction {
     Up,
     Down,
@@ -653,4 +2035,14 @@ pub trait Editing {
     fn move_cursor(&mut self, dir: Direction, steps: usize);
     fn add_char(&mut self, c: char);
     fn del_char(&mut self);
+
+    /// Inserts pasted text, breaking lines on newlines.
+    fn paste(&mut self, text: &str) {
+        for c in text.chars().filter(|c| *c != '\r') {
+            match c {
+                '\n' => self.new_line(),
+                c => self.add_char(c),
+            }
+        }
+    }
 }
@@ -0,0 +1,276 @@
+use std::collections::BTreeSet;
+
+use ratatui::{
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState,
+    },
+    Frame,
+};
+
+use crate::{
+    keymap::{self, Context},
+    runner::{self, Report},
+    state::{App, State},
+    theme::{self, Theme},
+};
+
+const TITLE: &str = "RUNNER";
+const ITEM_HEIGHT: usize = 4;
+
+#[derive(Clone)]
+pub struct RunnerView {
+    state: TableState,
+    data: App,
+    report: Report,
+    tag: Option<String>,
+    message: Option<String>,
+    scroll_state: ScrollbarState,
+    theme: Theme,
+}
+
+impl RunnerView {
+    pub fn new(app: &App) -> Self {
+        Self {
+            state: TableState::default().with_selected(0),
+            data: app.clone(),
+            report: Report::default(),
+            tag: None,
+            message: None,
+            scroll_state: ScrollbarState::default(),
+            theme: theme::current(),
+        }
+    }
+
+    pub fn run(&mut self, state: &mut State) {
+        self.report = runner::run(&mut self.data, self.tag.as_deref());
+        self.message = None;
+
+        self.scroll_state = match self.report.cases.len() {
+            0 => ScrollbarState::default(),
+            n => ScrollbarState::new((n - 1) * ITEM_HEIGHT),
+        };
+
+        state.insert(self.data.clone());
+        state.save();
+    }
+
+    /// Cycles the tag filter through every tag used by the app's requests, then back to all.
+    pub fn next_tag(&mut self, state: &mut State) {
+        let tags = self
+            .data
+            .requests
+            .iter()
+            .flat_map(|r| r.tags.iter().cloned())
+            .collect::<BTreeSet<_>>();
+
+        self.tag = match &self.tag {
+            None => tags.into_iter().next(),
+            Some(tag) => tags.into_iter().find(|t| t > tag),
+        };
+
+        self.run(state);
+    }
+
+    pub fn write_junit(&mut self) {
+        let path = format!("{}-junit.xml", self.data.name);
+        self.message = match std::fs::write(&path, self.report.junit()) {
+            Ok(_) => Some(format!("wrote {path}")),
+            Err(err) => Some(format!("failed to write {path}: {err}")),
+        };
+    }
+
+    /// Picks up what other instances saved, the report is of the requests as they were run.
+    pub fn sync(&mut self, state: &State) {
+        if let Some(app) = state.get(&self.data.name) {
+            self.data = app.clone();
+            self.message = Some("merged changes saved by another instance".into());
+        }
+    }
+
+    pub fn nais_app(&self) -> App {
+        self.data.clone()
+    }
+
+    pub fn down(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => match self.report.cases.len() {
+                0 | 1 => 0,
+                len if i >= len - 1 => 0,
+                _ => i + 1,
+            },
+            None => 0,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+    }
+
+    pub fn up(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => match self.report.cases.len() {
+                0 | 1 => 0,
+                len if i == 0 => len - 1,
+                _ => i - 1,
+            },
+            None => 0,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        self.theme = theme::current();
+        let layout = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(frame.size());
+
+        self.render_header(frame, layout[0]);
+        self.render_table(frame, layout[1]);
+        self.render_scrollbar(frame, layout[1]);
+        self.render_footer(frame, layout[2]);
+    }
+}
+
+impl RunnerView {
+    fn render_header(&mut self, frame: &mut Frame, layout: Rect) {
+        let passed = self.report.cases.len() - self.report.failures();
+        let title = format!(
+            "{TITLE} {} [{}] {passed}/{} passed",
+            self.data.name,
+            self.tag.as_deref().unwrap_or("all"),
+            self.report.cases.len(),
+        );
+
+        let info_header = Paragraph::new(Line::from(title))
+            .style(
+                Style::new()
+                    .fg(self.theme.header_boarder)
+                    .bg(self.theme.buffer_bg),
+            )
+            .centered()
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.theme.header_boarder)),
+            );
+        frame.render_widget(info_header, layout);
+    }
+
+    fn render_table(&mut self, frame: &mut Frame, layout: Rect) {
+        let header_style = Style::default()
+            .fg(self.theme.header_fg)
+            .bg(self.theme.header_bg);
+
+        let selected_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.theme.selected_style_fg);
+
+        let header = ["RESULT", "REQUEST", "TIME", "ASSERTIONS"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+
+        let rows = self.report.cases.iter().enumerate().map(|(i, case)| {
+            let color = match i % 2 {
+                0 => self.theme.normal_row,
+                _ => self.theme.alt_row,
+            };
+
+            let result = match (case.passed(), &case.error) {
+                (true, _) => Text::from("\nPASS\n").fg(self.theme.success),
+                (false, Some(_)) => Text::from("\nERROR\n").fg(self.theme.error),
+                (false, None) => Text::from("\nFAIL\n").fg(self.theme.error),
+            };
+
+            let request = format!("\n{} {}\n", case.request.method, case.request.path);
+            let time = format!("\n{} ms\n", case.elapsed.as_millis());
+
+            let mut details = vec![Line::default()];
+            details.extend(case.error.iter().map(|err| Line::from(err.clone())));
+            details.extend(case.checks.iter().map(|check| {
+                match check.passed {
+                    true => Line::from(format!("✓ {}", check.assert)),
+                    false => Line::from(format!("✗ {}: {}", check.assert, check.message))
+                        .fg(self.theme.error),
+                }
+            }));
+
+            let height = (details.len() + 1).max(ITEM_HEIGHT) as u16;
+
+            Row::new(vec![
+                Cell::from(result),
+                Cell::from(Text::from(request)),
+                Cell::from(Text::from(time)),
+                Cell::from(Text::from(details)),
+            ])
+            .style(Style::new().fg(self.theme.row_fg).bg(color))
+            .height(height)
+        });
+
+        let bar = " █ ";
+        let t = Table::new(
+            rows,
+            [
+                Constraint::Length(7),
+                Constraint::Percentage(40),
+                Constraint::Length(10),
+                Constraint::Min(20),
+            ],
+        )
+        .header(header)
+        .highlight_style(selected_style)
+        .highlight_symbol(Text::from(vec![
+            "".into(),
+            bar.into(),
+            bar.into(),
+            "".into(),
+        ]))
+        .bg(self.theme.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
+
+        frame.render_stateful_widget(t, layout, &mut self.state)
+    }
+
+    fn render_scrollbar(&mut self, frame: &mut Frame, layout: Rect) {
+        frame.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            layout.inner(Margin {
+                vertical: 1,
+                horizontal: 1,
+            }),
+            &mut self.scroll_state,
+        );
+    }
+
+    fn render_footer(&mut self, frame: &mut Frame, layout: Rect) {
+        let text = match &self.message {
+            Some(message) => message.clone(),
+            None => keymap::current().footer(Context::Runner),
+        };
+
+        let info_footer = Paragraph::new(Line::from(text))
+            .style(
+                Style::new()
+                    .fg(self.theme.footer_boarder)
+                    .bg(self.theme.buffer_bg),
+            )
+            .centered()
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.theme.footer_boarder)),
+            );
+        frame.render_widget(info_footer, layout);
+    }
+}
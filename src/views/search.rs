@@ -0,0 +1,282 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{state::State, theme};
+
+use super::centered_rect;
+
+/// A fuzzy filter, typed after `/` in a table or the request tree.
+#[derive(Clone, Default)]
+pub struct Search {
+    pub query: String,
+    /// Keys go to the query instead of the view while typing.
+    pub typing: bool,
+}
+
+impl Search {
+    pub fn start(&mut self) {
+        self.typing = true;
+    }
+
+    /// Keeps the filter and goes back to navigating the results.
+    pub fn confirm(&mut self) {
+        self.typing = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.typing = false;
+    }
+
+    pub fn add_char(&mut self, c: char) {
+        self.query.push(c);
+    }
+
+    pub fn del_char(&mut self) {
+        self.query.pop();
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.typing || !self.query.is_empty()
+    }
+
+    /// Everything matches an empty query.
+    pub fn matches(&self, text: &str) -> Option<Match> {
+        fuzzy(&self.query, text)
+    }
+
+    /// The query as shown in the footer, like `/sak_` while typing.
+    pub fn footer(&self) -> Option<String> {
+        match (self.typing, self.query.is_empty()) {
+            (true, _) => Some(format!("/{}_ (enter) keep (esc) clear", self.query)),
+            (false, false) => Some(format!("/{} (/) change (esc) clear", self.query)),
+            (false, true) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    /// The indices of the matched chars in the text.
+    pub positions: Vec<usize>,
+}
+
+/// Matches the chars of the query in order, ignoring case and whitespace in the query.
+/// Consecutive chars and chars at the start of a word score higher, so `sak` ranks
+/// `/api/sak` above `/api/status/aktiv`.
+pub fn fuzzy(query: &str, text: &str) -> Option<Match> {
+    let mut query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+
+    let mut found = Match::default();
+    let mut prev: Option<char> = None;
+    for (i, c) in text.chars().enumerate() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            found.score += 1;
+            if found.positions.last().is_some_and(|&last| last + 1 == i) {
+                found.score += 5;
+            }
+            if prev.is_none_or(|p| !p.is_alphanumeric()) {
+                found.score += 3;
+            }
+            found.positions.push(i);
+            query.next();
+        }
+        prev = Some(c);
+    }
+
+    match query.peek() {
+        Some(_) => None,
+        None => Some(found),
+    }
+}
+
+/// The text split in spans, with the matched chars in the style.
+pub fn highlight(text: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span> = vec![];
+    let mut run = String::new();
+    let mut matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_match = positions.contains(&i);
+        if is_match != matched && !run.is_empty() {
+            spans.push(styled(std::mem::take(&mut run), matched, style));
+        }
+        matched = is_match;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(styled(run, matched, style));
+    }
+    spans
+}
+
+fn styled(text: String, matched: bool, style: Style) -> Span<'static> {
+    match matched {
+        true => Span::styled(text, style),
+        false => Span::raw(text),
+    }
+}
+
+/// The style of matched chars.
+pub fn match_style() -> Style {
+    Style::new()
+        .fg(theme::current().matched)
+        .add_modifier(Modifier::BOLD)
+}
+
+/// Where the finder jumps to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    App(String),
+    Request { app: String, id: String },
+}
+
+/// Finds any app or saved request across all apps, opened with ctrl-p.
+pub struct Finder {
+    pub search: Search,
+    targets: Vec<(Target, String)>,
+    state: ListState,
+}
+
+impl Finder {
+    pub fn new(state: &State) -> Self {
+        let mut targets = vec![];
+        for app in state.values() {
+            targets.push((Target::App(app.name.clone()), app.name.clone()));
+            for req in app.ordered() {
+                let label = format!("{} {} {} {}", app.name, req.method, req.path, req.desc);
+                let target = Target::Request {
+                    app: app.name.clone(),
+                    id: req.id.clone(),
+                };
+                targets.push((target, label.trim_end().to_string()));
+            }
+        }
+
+        Self {
+            search: Search {
+                query: String::new(),
+                typing: true,
+            },
+            targets,
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    /// The targets matching the query, best first.
+    fn results(&self) -> Vec<(&Target, &str, Match)> {
+        let mut results = self
+            .targets
+            .iter()
+            .filter_map(|(target, label)| {
+                Some((target, label.as_str(), self.search.matches(label)?))
+            })
+            .collect::<Vec<_>>();
+        results.sort_by_key(|(_, _, m)| -m.score);
+        results
+    }
+
+    pub fn add_char(&mut self, c: char) {
+        self.search.add_char(c);
+        self.state.select(Some(0));
+    }
+
+    pub fn del_char(&mut self) {
+        self.search.del_char();
+        self.state.select(Some(0));
+    }
+
+    pub fn down(&mut self) {
+        let len = self.results().len();
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some((i + 1).min(len.saturating_sub(1))));
+    }
+
+    pub fn up(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some(i.saturating_sub(1)));
+    }
+
+    pub fn selected(&self) -> Option<Target> {
+        let results = self.results();
+        let (target, _, _) = results.get(self.state.selected().unwrap_or(0))?;
+        Some((*target).clone())
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let area = centered_rect(60, 60, frame.size());
+        let style = match_style();
+        let items = self
+            .results()
+            .into_iter()
+            .map(|(target, label, m)| {
+                let mut spans = highlight(label, &m.positions, style);
+                if let Target::App(_) = target {
+                    spans.push(" (app)".fg(theme::current().muted));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+
+        let [input, list] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(3)]).areas(area);
+
+        let border = Style::new().fg(theme::current().header_boarder);
+        let query = Paragraph::new(self.search.query.as_str()).block(
+            Block::bordered()
+                .title("Go to app or request (enter) jump (esc) close")
+                .border_style(border),
+        );
+        let list_widget = List::new(items)
+            .block(Block::bordered().border_style(border))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(query, input);
+        frame.render_stateful_widget(list_widget, list, &mut self.state);
+        frame.set_cursor(input.x + self.search.query.len() as u16 + 1, input.y + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_in_order() {
+        let m = fuzzy("sak", "/api/sak").unwrap();
+        assert_eq!(m.positions, vec![5, 6, 7]);
+
+        assert!(fuzzy("kas", "/api/sak").is_none());
+        assert!(fuzzy("GET sak", "get /api/sak").is_some());
+        assert_eq!(fuzzy("", "anything").unwrap().score, 0);
+    }
+
+    #[test]
+    fn fuzzy_prefers_words_and_runs() {
+        let word = fuzzy("sak", "/api/sak").unwrap();
+        let scattered = fuzzy("sak", "/api/status/aktiv").unwrap();
+
+        assert!(word.score > scattered.score);
+    }
+
+    #[test]
+    fn highlight_splits_matched_runs() {
+        let spans = highlight("/api/sak", &[5, 6, 7], Style::new());
+        let texts = spans.iter().map(|s| s.content.as_ref()).collect::<Vec<_>>();
+
+        assert_eq!(texts, vec!["/api/", "sak"]);
+    }
+}
@@ -0,0 +1,530 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::migrate;
+use crate::state::{
+    App, Assert, BodyMode, Extract, Header, Method, Operation, Param, Request, Settings, State,
+};
+
+/// The directory of shared requests, found in the working directory or one of its parents.
+/// It holds a folder per app with a YAML file per request, meant to be committed and reviewed.
+pub const DIR: &str = ".helved";
+
+pub fn find() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(DIR))
+        .find(|dir| dir.is_dir())
+}
+
+/// A request as written to the workspace. The history stays in the user's own state, and so do
+/// the values of secret headers and params (see `Header::is_secret` and `Param::is_secret`),
+/// which are written empty.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct SharedRequest {
+    #[serde(deserialize_with = "id")]
+    id: String,
+    #[serde(default)]
+    created: u64,
+    #[serde(default)]
+    modified: u64,
+    method: Method,
+    path: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    desc: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    folder: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    query: Vec<Param>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<Header>,
+    #[serde(default)]
+    body_mode: BodyMode,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    form: Vec<Param>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extract: Vec<Extract>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    asserts: Vec<Assert>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Settings::is_empty")]
+    settings: Settings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    operation: Option<Operation>,
+}
+
+impl From<&Request> for SharedRequest {
+    fn from(req: &Request) -> Self {
+        let headers = req
+            .headers
+            .iter()
+            .map(|h| match h.is_secret() {
+                true => Header {
+                    value: String::new(),
+                    ..h.clone()
+                },
+                false => h.clone(),
+            })
+            .collect();
+        let blank = |params: &[Param]| {
+            params
+                .iter()
+                .map(|p| match p.is_secret() {
+                    true => Param {
+                        value: String::new(),
+                        ..p.clone()
+                    },
+                    false => p.clone(),
+                })
+                .collect()
+        };
+
+        Self {
+            id: req.id.clone(),
+            created: req.created,
+            modified: req.modified,
+            method: req.method.clone(),
+            path: req.path.clone(),
+            desc: req.desc.clone(),
+            folder: req.folder.clone(),
+            query: blank(&req.query),
+            headers,
+            body_mode: req.body_mode,
+            body: req.body.clone(),
+            form: blank(&req.form),
+            extract: req.extract.clone(),
+            asserts: req.asserts.clone(),
+            tags: req.tags.clone(),
+            settings: req.settings.clone(),
+            operation: req.operation.clone(),
+        }
+    }
+}
+
+impl SharedRequest {
+    /// The request, with the history and secret header and param values of the user's own copy.
+    /// A secret header value is taken from the header with the same key and count, like the
+    /// second `Cookie`, or else the secret header in the same place, as when the key was renamed.
+    /// A secret param value is taken from the param with the same key and count.
+    fn into_request(self, local: Option<&Request>) -> Request {
+        let mut seen = BTreeMap::<String, usize>::new();
+        let headers = self
+            .headers
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let count = seen.entry(h.key.to_ascii_lowercase()).or_default();
+                let nth = *count;
+                *count += 1;
+                match local.filter(|_| h.is_secret()) {
+                    Some(local) => Header {
+                        value: local
+                            .headers
+                            .iter()
+                            .filter(|l| l.key.eq_ignore_ascii_case(&h.key))
+                            .nth(nth)
+                            .or_else(|| local.headers.get(i).filter(|l| l.is_secret()))
+                            .map(|l| l.value.clone())
+                            .unwrap_or_default(),
+                        ..h
+                    },
+                    None => h,
+                }
+            })
+            .collect();
+
+        Request {
+            id: self.id,
+            created: self.created,
+            modified: self.modified,
+            method: self.method,
+            path: self.path,
+            query: restore(self.query, local.map(|l| l.query.as_slice())),
+            desc: self.desc,
+            folder: self.folder,
+            headers,
+            body: self.body,
+            body_mode: self.body_mode,
+            form: restore(self.form, local.map(|l| l.form.as_slice())),
+            extract: self.extract,
+            asserts: self.asserts,
+            tags: self.tags,
+            operation: self.operation,
+            settings: self.settings,
+            history: local.map(|l| l.history.clone()).unwrap_or_default(),
+            shared: true,
+            extra: local.map(|l| l.extra.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// The params with the values of the secret ones taken from the user's own copy, by key and
+/// count like the headers.
+fn restore(params: Vec<Param>, local: Option<&[Param]>) -> Vec<Param> {
+    let Some(local) = local else {
+        return params;
+    };
+    let mut seen = BTreeMap::<String, usize>::new();
+    params
+        .into_iter()
+        .map(|p| {
+            let count = seen.entry(p.key.clone()).or_default();
+            let nth = *count;
+            *count += 1;
+            match p.is_secret() {
+                true => Param {
+                    value: local
+                        .iter()
+                        .filter(|l| l.key == p.key)
+                        .nth(nth)
+                        .map(|l| l.value.clone())
+                        .unwrap_or_default(),
+                    ..p
+                },
+                false => p,
+            }
+        })
+        .collect()
+}
+
+/// Adds the shared requests to the state, replacing the user's own copies of them.
+/// Apps that are only in the workspace are added, their pods and hosts are found on refresh.
+/// Files that can't be read are skipped, and returned with what was wrong with them.
+pub fn load(root: &Path, state: &mut State) -> Result<Vec<String>> {
+    let mut skipped = vec![];
+    for dir in entries(root)?.into_iter().filter(|path| path.is_dir()) {
+        let Some(name) = dir.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if state.get(&name).is_none() {
+            state.insert(App::named(&name));
+        }
+        let app = state.get_mut(&name).expect("app was just inserted");
+
+        for path in yaml_files(&dir)? {
+            let shared = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|yaml| {
+                    serde_yaml::from_str::<SharedRequest>(&yaml).map_err(|err| err.to_string())
+                });
+            let shared = match shared {
+                Ok(shared) => shared,
+                Err(err) => {
+                    skipped.push(format!("skipped {}: {err}", path.display()));
+                    continue;
+                }
+            };
+            let local = app.requests.iter().find(|r| r.id == shared.id).cloned();
+            app.add_request(shared.into_request(local.as_ref()));
+        }
+    }
+    Ok(skipped)
+}
+
+/// The ids of the requests with a file in the workspace.
+pub fn ids(root: &Path) -> BTreeSet<String> {
+    let dirs = entries(root).unwrap_or_default();
+    dirs.iter()
+        .flat_map(|dir| files(dir).unwrap_or_default().into_keys())
+        .collect()
+}
+
+/// Writes each shared request to its own file, and removes the files of requests that are no
+/// longer shared. Files are only written when their content changes, and keep their name when
+/// the request is edited. `seen` are the ids of the files as last loaded or saved, a shared
+/// request whose file is gone since was removed by someone else and is no longer shared.
+/// Returns whether any were.
+pub fn save(root: &Path, state: &mut State, seen: &mut BTreeSet<String>) -> Result<bool> {
+    let mut removed = false;
+    for name in state
+        .keys()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>()
+    {
+        let app = state.get_mut(&name).expect("app of the state");
+        let dir = root.join(&app.name);
+        let mut files = files(&dir)?;
+
+        for req in &mut app.requests {
+            match (req.shared, files.get(&req.id)) {
+                (true, None) if seen.contains(&req.id) => {
+                    req.shared = false;
+                    req.touch();
+                    seen.remove(&req.id);
+                    removed = true;
+                }
+                (true, existing) => {
+                    let path = existing.cloned().unwrap_or_else(|| file_name(&dir, req));
+                    let yaml = serde_yaml::to_string(&SharedRequest::from(&*req))?;
+                    if fs::read_to_string(&path).ok().as_ref() != Some(&yaml) {
+                        fs::create_dir_all(&dir)?;
+                        fs::write(&path, yaml)
+                            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+                        files.insert(req.id.clone(), path);
+                    }
+                    seen.insert(req.id.clone());
+                }
+                (false, Some(path)) => {
+                    fs::remove_file(path)?;
+                    seen.remove(&req.id);
+                }
+                (false, None) => {}
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Removes the file of a request that was deleted, if it was shared.
+pub fn remove(root: &Path, app: &str, id: &str) -> Result<()> {
+    if let Some(path) = files(&root.join(app))?.get(id) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// The request files of an app by request id, in the order of their names.
+fn files(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    for path in yaml_files(dir)? {
+        #[derive(Deserialize)]
+        struct Id {
+            #[serde(deserialize_with = "id")]
+            id: String,
+        }
+        if let Ok(Id { id }) = serde_yaml::from_str(&fs::read_to_string(&path)?) {
+            files.insert(id, path);
+        }
+    }
+    Ok(files)
+}
+
+/// The YAML files in the directory sorted by name.
+fn yaml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = entries(dir)?;
+    paths.retain(|path| {
+        matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml" | "yml")
+        )
+    });
+    Ok(paths)
+}
+
+/// A request id, where files shared before ids were UUIDs have the old number.
+fn id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Uuid(String),
+        Legacy(u64),
+    }
+    Ok(match Id::deserialize(deserializer)? {
+        Id::Uuid(id) => id,
+        Id::Legacy(n) => migrate::legacy_id(n),
+    })
+}
+
+/// The paths in the directory sorted by name, none if it doesn't exist.
+fn entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(vec![]);
+    };
+    let mut paths = entries
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// A new file name like `get-api-sak.yaml`, numbered if it is taken.
+fn file_name(dir: &Path, req: &Request) -> PathBuf {
+    let slug = format!("{} {}", req.method, req.path)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("{slug}.yaml")),
+            n => dir.join(format!("{slug}-{n}.yaml")),
+        })
+        .find(|path| !path.exists())
+        .expect("a free file name")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{state::Sample, util::TempDir};
+
+    use super::*;
+
+    fn request(id: &str, path: &str) -> Request {
+        let mut req = Request::new(
+            Method::Get,
+            path,
+            vec![
+                Header::new("Accept".into(), "application/json".into()),
+                Header {
+                    secret: true,
+                    ..Header::new("X-Api-Key".into(), "hemmelig".into())
+                },
+            ],
+            "",
+        );
+        req.id = id.into();
+        req.created = 1;
+        req.modified = 2;
+        req.shared = true;
+        req.history = vec![Sample {
+            at: 0,
+            status: 200,
+            ms: 12,
+            load: None,
+        }];
+        req
+    }
+
+    #[test]
+    fn shared_requests_round_trip_without_secrets() {
+        let root = TempDir::new("workspace");
+        let mut app = App::named("utsjekk");
+        app.requests = vec![request("a", "/api/sak"), request("b", "/api/sak/{id}")];
+        app.requests[1].shared = false;
+        let mut state = State::default();
+        state.insert(app.clone());
+
+        save(&root, &mut state, &mut BTreeSet::new()).unwrap();
+
+        let yaml = fs::read_to_string(root.join("utsjekk/get-api-sak.yaml")).unwrap();
+        assert!(!yaml.contains("hemmelig"));
+        assert!(!yaml.contains("history"));
+        assert_eq!(files(&root.join("utsjekk")).unwrap().len(), 1);
+
+        let mut loaded = State::default();
+        load(&root, &mut loaded).unwrap();
+        let shared = &loaded.get("utsjekk").unwrap().requests;
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].headers[1].value, "");
+        assert!(shared[0].history.is_empty());
+
+        let mut local = State::default();
+        local.insert(app);
+        load(&root, &mut local).unwrap();
+        let merged = &local.get("utsjekk").unwrap().requests[0];
+        assert_eq!(merged, &request("a", "/api/sak"));
+
+        let mut unshared = local.clone();
+        unshared.get_mut("utsjekk").unwrap().requests[0].shared = false;
+        save(&root, &mut unshared, &mut BTreeSet::new()).unwrap();
+        assert!(files(&root.join("utsjekk")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn files_removed_by_others_are_not_written_back() {
+        let root = TempDir::new("removed");
+        let mut app = App::named("utsjekk");
+        app.requests = vec![request("a", "/api/sak"), request("b", "/api/sak/{id}")];
+        let mut state = State::default();
+        state.insert(app);
+        save(&root, &mut state, &mut BTreeSet::new()).unwrap();
+        let mut seen = ids(&root);
+
+        fs::remove_file(root.join("utsjekk/get-api-sak.yaml")).unwrap();
+        let new = request("c", "/api/vedtak");
+        state.get_mut("utsjekk").unwrap().requests.push(new);
+
+        assert!(save(&root, &mut state, &mut seen).unwrap());
+        assert!(!root.join("utsjekk/get-api-sak.yaml").exists());
+        assert!(!state.get("utsjekk").unwrap().requests[0].shared);
+        assert_eq!(seen, BTreeSet::from(["b".to_string(), "c".to_string()]));
+    }
+
+    #[test]
+    fn secrets_are_restored_by_key_and_place() {
+        let secret = |key: &str, value: &str| Header {
+            secret: true,
+            ..Header::new(key.into(), value.into())
+        };
+        let mut local = request("a", "/api/sak");
+        local.headers = vec![
+            secret("Cookie", "first"),
+            secret("Cookie", "second"),
+            secret("X-Api-Key", "hemmelig"),
+        ];
+        let mut renamed = local.clone();
+        renamed.headers[2].key = "X-Key".into();
+
+        let shared = SharedRequest::from(&renamed).into_request(Some(&local));
+
+        let values = shared
+            .headers
+            .iter()
+            .map(|h| h.value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec!["first", "second", "hemmelig"]);
+    }
+
+    #[test]
+    fn credentials_are_secret_without_the_flag() {
+        let mut local = request("a", "/api/sak");
+        local.headers = vec![
+            Header::new("Authorization".into(), "Bearer hemmelig".into()),
+            Header::new("Cookie".into(), "session=hemmelig".into()),
+            Header::new("X-Api-Key".into(), "hemmelig".into()),
+            Header::new("Accept".into(), "application/json".into()),
+        ];
+        local.query = vec![
+            Param::new("access_token", "hemmelig"),
+            Param::new("page", "2"),
+        ];
+        local.form = vec![Param::new("client_secret", "hemmelig")];
+
+        let shared = SharedRequest::from(&local);
+        let yaml = serde_yaml::to_string(&shared).unwrap();
+        assert!(!yaml.contains("hemmelig"));
+        assert!(yaml.contains("application/json"));
+        assert!(yaml.contains("page"));
+
+        assert_eq!(shared.into_request(Some(&local)), local);
+    }
+
+    #[test]
+    fn bad_files_are_skipped() {
+        let root = TempDir::new("bad-files");
+        fs::create_dir_all(root.join("utsjekk")).unwrap();
+        fs::write(
+            root.join("utsjekk/a.yaml"),
+            "id: a\nmethod: Get\npath: /api/sak\n",
+        )
+        .unwrap();
+        fs::write(root.join("utsjekk/b.yaml"), "id: b\nmethod: [\n").unwrap();
+
+        let mut state = State::default();
+        let skipped = load(&root, &mut state).unwrap();
+
+        assert_eq!(state.get("utsjekk").unwrap().requests.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("b.yaml"));
+    }
+
+    #[test]
+    fn numbered_files_get_legacy_ids() {
+        let yaml = "id: 7\nmethod: Get\npath: /api/sak\n";
+        let shared = serde_yaml::from_str::<SharedRequest>(yaml).unwrap();
+
+        assert_eq!(shared.id, migrate::legacy_id(7));
+    }
+}